@@ -145,6 +145,7 @@ impl StartCommand {
         if let Some(data_home) = &self.data_home {
             opts.storage.store = ObjectStoreConfig::File(FileConfig {
                 data_home: data_home.clone(),
+                atomic_write_dir: None,
             });
         }
 
@@ -0,0 +1,1967 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Anonymous usage data reporting of GreptimeDB.
+
+pub mod error;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use common_runtime::error::{Error, Result};
+use common_runtime::{RepeatedTask, TaskFunction};
+use common_telemetry::{debug, info, warn};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{Client, Proxy, Response};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use tokio::sync::Mutex;
+
+/// The URL to report telemetry data.
+pub const TELEMETRY_URL: &str = "https://telemetry.greptimestats.com/db/otel/statistics";
+/// The local installation uuid cache file.
+const UUID_FILE_NAME: &str = ".greptimedb-telemetry-uuid";
+/// The directory under the platform data directory to store the uuid file.
+const DATA_DIR_NAME: &str = "greptimedb";
+/// The local telemetry opt-in state file, stored in the same directory as the uuid
+/// file, see [uuid_file_path()].
+const STATE_FILE_NAME: &str = ".greptimedb-telemetry-state";
+
+/// Environment variable to disable telemetry without rebuilding, takes `1` or `true`.
+pub const TELEMETRY_DISABLED_ENV: &str = "GREPTIMEDB_TELEMETRY_DISABLED";
+
+/// The default interval of reporting telemetry data to greptime cloud.
+pub static TELEMETRY_INTERVAL: Duration = Duration::from_secs(60 * 30);
+/// The default max fraction of the interval to randomly add or subtract, so
+/// nodes started together don't report at the same time.
+pub const DEFAULT_TELEMETRY_JITTER: f64 = 0.1;
+/// Delays before retrying to get the uuid after consecutive failures, the
+/// last one is used for all following retries.
+const UUID_RETRY_BACKOFF: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(30 * 60),
+];
+/// Counter of telemetry reports attempted, including ones failed to encode.
+pub const METRIC_TELEMETRY_REPORTS_TOTAL: &str = "greptimedb_telemetry_reports_total";
+/// Counter of telemetry reports failed, labeled by [METRIC_TELEMETRY_FAILURE_REASON].
+pub const METRIC_TELEMETRY_REPORT_FAILURES_TOTAL: &str =
+    "greptimedb_telemetry_report_failures_total";
+/// Label of the failure reason, `network`, `status`, or `encode` if the data
+/// can't be encoded.
+pub const METRIC_TELEMETRY_FAILURE_REASON: &str = "reason";
+/// The default connect timeout to greptime cloud.
+const GREPTIMEDB_TELEMETRY_CLIENT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// The default request timeout to greptime cloud.
+const GREPTIMEDB_TELEMETRY_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options of the HTTP client reporting telemetry data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelemetryClientConfig {
+    /// Timeout to connect to the telemetry endpoint.
+    pub connect_timeout: Duration,
+    /// Timeout of the whole request, including connecting.
+    pub timeout: Duration,
+}
+
+impl Default for TelemetryClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: GREPTIMEDB_TELEMETRY_CLIENT_CONNECT_TIMEOUT,
+            timeout: GREPTIMEDB_TELEMETRY_CLIENT_TIMEOUT,
+        }
+    }
+}
+
+impl TelemetryClientConfig {
+    /// Returns an error if any timeout is zero.
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            !self.connect_timeout.is_zero(),
+            error::InvalidClientConfigSnafu {
+                reason: "connect_timeout must be greater than 0",
+            }
+        );
+        ensure!(
+            !self.timeout.is_zero(),
+            error::InvalidClientConfigSnafu {
+                reason: "timeout must be greater than 0",
+            }
+        );
+        Ok(())
+    }
+}
+
+/// The task reporting telemetry data repeatedly.
+pub enum GreptimeDBTelemetryTask {
+    Enable {
+        task: RepeatedTask<Error>,
+        /// The telemetry shared by the repeated task and [GreptimeDBTelemetryTask::report_now()].
+        telemetry: Arc<Mutex<GreptimeDBTelemetry>>,
+        /// Shared with the telemetry, so it can be read without waiting for a report.
+        last_report_unix_ms: Arc<AtomicU64>,
+    },
+    Disable,
+}
+
+impl GreptimeDBTelemetryTask {
+    /// Returns a task running `telemetry` about every `interval`, or a disabled task if
+    /// telemetry is disabled by the [TELEMETRY_DISABLED_ENV] environment variable.
+    ///
+    /// The interval is jittered by [DEFAULT_TELEMETRY_JITTER].
+    pub fn enable(interval: Duration, telemetry: GreptimeDBTelemetry) -> Self {
+        Self::enable_with_jitter(interval, DEFAULT_TELEMETRY_JITTER, telemetry)
+    }
+
+    /// Same as [GreptimeDBTelemetryTask::enable()] but jitters the interval by up to
+    /// `jitter` of it, in range `[0.0, 1.0)`. The delay is drawn again before every
+    /// report, including the first one.
+    ///
+    /// An invalid `jitter`, e.g. NaN, is rejected and [DEFAULT_TELEMETRY_JITTER]
+    /// is used instead.
+    pub fn enable_with_jitter(
+        interval: Duration,
+        jitter: f64,
+        telemetry: GreptimeDBTelemetry,
+    ) -> Self {
+        if disabled_by_env() {
+            info!(
+                "Telemetry is disabled by environment variable {}",
+                TELEMETRY_DISABLED_ENV
+            );
+            return GreptimeDBTelemetryTask::Disable;
+        }
+
+        let jitter = if (0.0..1.0).contains(&jitter) {
+            jitter
+        } else {
+            warn!(
+                "Invalid telemetry jitter {}, expect a value in [0.0, 1.0), use {} instead",
+                jitter, DEFAULT_TELEMETRY_JITTER
+            );
+            DEFAULT_TELEMETRY_JITTER
+        };
+        let last_report_unix_ms = telemetry.last_report_unix_ms.clone();
+        let telemetry = Arc::new(Mutex::new(telemetry));
+        let task = RepeatedTask::new(
+            interval,
+            Box::new(SharedGreptimeDBTelemetry(telemetry.clone())),
+        )
+        .with_jitter(jitter);
+        GreptimeDBTelemetryTask::Enable {
+            task,
+            telemetry,
+            last_report_unix_ms,
+        }
+    }
+
+    /// Returns the time of the last report that got a successful response, `None`
+    /// if there is none yet or the task is disabled.
+    pub fn last_successful_report(&self) -> Option<SystemTime> {
+        match self {
+            GreptimeDBTelemetryTask::Enable {
+                last_report_unix_ms,
+                ..
+            } => unix_ms_to_time(last_report_unix_ms),
+            GreptimeDBTelemetryTask::Disable => None,
+        }
+    }
+
+    /// Reports telemetry data once immediately, without changing the schedule of
+    /// the repeated task.
+    ///
+    /// It's safe to call this while the repeated task is running, the two reports
+    /// run one after another.
+    pub async fn report_now(&self) -> Result<()> {
+        match self {
+            GreptimeDBTelemetryTask::Enable { telemetry, .. } => {
+                telemetry.lock().await.call().await
+            }
+            GreptimeDBTelemetryTask::Disable => Ok(()),
+        }
+    }
+
+    pub fn disable() -> Self {
+        GreptimeDBTelemetryTask::Disable
+    }
+
+    pub fn start(&self, runtime: common_runtime::Runtime) -> Result<()> {
+        match self {
+            GreptimeDBTelemetryTask::Enable { task, .. } => task.start(runtime),
+            GreptimeDBTelemetryTask::Disable => Ok(()),
+        }
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        match self {
+            GreptimeDBTelemetryTask::Enable { task, .. } => task.stop().await,
+            GreptimeDBTelemetryTask::Disable => Ok(()),
+        }
+    }
+}
+
+/// Returns the time stored as milliseconds since the unix epoch, `None` if it is 0.
+fn unix_ms_to_time(unix_ms: &AtomicU64) -> Option<SystemTime> {
+    match unix_ms.load(Ordering::Relaxed) {
+        0 => None,
+        millis => Some(UNIX_EPOCH + Duration::from_millis(millis)),
+    }
+}
+
+fn disabled_by_env() -> bool {
+    env::var(TELEMETRY_DISABLED_ENV)
+        .map(|value| is_disabled_value(&value))
+        .unwrap_or(false)
+}
+
+fn is_disabled_value(value: &str) -> bool {
+    let value = value.trim();
+    value == "1" || value.eq_ignore_ascii_case("true")
+}
+
+/// Telemetry data to report.
+#[derive(Serialize, Deserialize, Debug)]
+struct StatisticData {
+    /// Operating system name, such as `linux`, `windows`, `macos`.
+    pub os: String,
+    /// The greptimedb version.
+    pub version: String,
+    /// The architecture of the CPU, such as `x86`, `x86_64` or `aarch`.
+    pub arch: String,
+    /// The running mode, `standalone` or `distributed`.
+    pub mode: Mode,
+    /// The git commit revision of greptimedb.
+    pub git_commit: String,
+    /// The node number.
+    pub nodes: Option<i32>,
+    /// Size in bytes of the largest region, omitted if unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_region_bytes: Option<i64>,
+    /// The number of regions, omitted if unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regions: Option<i64>,
+    /// The number of tables, omitted if unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tables: Option<i64>,
+    /// Label of the default compaction strategy, such as `leveled_time_window` or `twcs`.
+    pub compaction_strategy: String,
+    /// Label of the WAL sync policy, such as `sync_write` or `no_sync`.
+    pub wal_sync_policy: String,
+    /// Custom metadata attached by the collector, omitted if empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra: Option<BTreeMap<String, String>>,
+    /// The local installation uuid.
+    pub uuid: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Distributed,
+    Standalone,
+}
+
+/// Whether the user allows reporting telemetry data.
+///
+/// The state is persisted alongside the uuid file. Telemetry data is only
+/// reported when the state is explicitly [TelemetryState::Enabled].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryState {
+    /// The user allows reporting.
+    Enabled,
+    /// The user refuses reporting.
+    Disabled,
+    /// The user hasn't made a choice yet.
+    Undecided,
+}
+
+impl TelemetryState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TelemetryState::Enabled => "enabled",
+            TelemetryState::Disabled => "disabled",
+            TelemetryState::Undecided => "undecided",
+        }
+    }
+}
+
+impl FromStr for TelemetryState {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim() {
+            "enabled" => Ok(TelemetryState::Enabled),
+            "disabled" => Ok(TelemetryState::Disabled),
+            "undecided" => Ok(TelemetryState::Undecided),
+            other => Err(format!("unknown telemetry state: {other}")),
+        }
+    }
+}
+
+/// Returns the persisted telemetry state, [TelemetryState::Undecided] if the
+/// user hasn't made a choice.
+pub fn get_telemetry_state() -> TelemetryState {
+    read_telemetry_state(&default_state_path())
+}
+
+/// Persists the telemetry state chosen by the user.
+pub fn set_telemetry_state(state: TelemetryState) -> std::io::Result<()> {
+    write_telemetry_state(&default_state_path(), state)
+}
+
+fn default_state_path() -> PathBuf {
+    uuid_file_path().with_file_name(STATE_FILE_NAME)
+}
+
+fn read_telemetry_state(path: &Path) -> TelemetryState {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content.parse().unwrap_or_else(|e| {
+            debug!("Failed to parse telemetry state file {:?}: {}", path, e);
+            TelemetryState::Undecided
+        }),
+        Err(_) => TelemetryState::Undecided,
+    }
+}
+
+fn write_telemetry_state(path: &Path, state: TelemetryState) -> std::io::Result<()> {
+    std::fs::write(path, state.as_str().as_bytes())
+}
+
+/// Label for configurations a collector doesn't know.
+pub const UNKNOWN_CONFIG_LABEL: &str = "unknown";
+
+/// Region and table statistics of a report, `None` if unknown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WorkloadStats {
+    /// Size in bytes of the largest region.
+    pub max_region_bytes: Option<i64>,
+    /// The number of regions.
+    pub regions: Option<i64>,
+    /// The number of tables.
+    pub tables: Option<i64>,
+}
+
+/// Collects the data to report.
+///
+/// Each component implements its own collector.
+#[async_trait::async_trait]
+pub trait Collector {
+    fn get_version(&self) -> String {
+        env!("CARGO_PKG_VERSION").to_string()
+    }
+
+    fn get_git_hash(&self) -> String {
+        env!("GIT_COMMIT").to_string()
+    }
+
+    fn get_os(&self) -> String {
+        env::consts::OS.to_string()
+    }
+
+    fn get_arch(&self) -> String {
+        env::consts::ARCH.to_string()
+    }
+
+    fn get_compaction_strategy(&self) -> String {
+        UNKNOWN_CONFIG_LABEL.to_string()
+    }
+
+    fn get_wal_sync_policy(&self) -> String {
+        UNKNOWN_CONFIG_LABEL.to_string()
+    }
+
+    /// Returns custom key/value metadata to attach to the report, such as the
+    /// deployment tier.
+    fn get_extra_metadata(&self) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+
+    fn get_mode(&self) -> Mode;
+
+    /// Returns the number of consecutive failures to get the uuid.
+    fn get_retry(&self) -> i32;
+
+    /// Records a failure to get the uuid, along with the time of the failure.
+    fn inc_retry(&mut self);
+
+    /// Clears the failures recorded by [Collector::inc_retry()].
+    fn reset_retry(&mut self);
+
+    /// Returns the time of the last failure to get the uuid.
+    fn get_last_retry_time(&self) -> Option<Instant>;
+
+    fn set_uuid_cache(&mut self, uuid: String);
+
+    fn get_uuid_cache(&self) -> Option<String>;
+
+    async fn get_nodes(&self) -> Option<i32>;
+
+    /// Returns the size in bytes of the largest region, `None` if unknown.
+    async fn get_max_region_bytes(&self) -> Option<i64> {
+        None
+    }
+
+    /// Returns the number of regions, `None` if unknown.
+    async fn get_regions(&self) -> Option<i64> {
+        None
+    }
+
+    /// Returns the number of tables, `None` if unknown.
+    async fn get_tables(&self) -> Option<i64> {
+        None
+    }
+
+    /// Returns the region and table statistics of one report.
+    ///
+    /// Collects them from the separate getters by default. Collectors that can
+    /// derive all of them from one snapshot should override this.
+    async fn get_workload_stats(&self) -> WorkloadStats {
+        WorkloadStats {
+            max_region_bytes: self.get_max_region_bytes().await,
+            regions: self.get_regions().await,
+            tables: self.get_tables().await,
+        }
+    }
+
+    fn get_uuid(&mut self) -> Option<String> {
+        get_uuid_with_backoff(self, default_get_uuid)
+    }
+}
+
+/// A [Collector] combining several collectors, e.g. the metasrv and datanode
+/// views of a distributed cluster.
+///
+/// The node, region and table counts are summed over all collectors, skipping unknown
+/// ones, and the largest region is the largest over all collectors. Everything else, including
+/// the uuid, is delegated to the first collector.
+pub struct CompositeCollector {
+    collectors: Vec<Box<dyn Collector + Send + Sync>>,
+}
+
+impl CompositeCollector {
+    /// Returns a collector combining `collectors`.
+    ///
+    /// # Panics
+    /// Panics if `collectors` is empty.
+    pub fn new(collectors: Vec<Box<dyn Collector + Send + Sync>>) -> Self {
+        assert!(
+            !collectors.is_empty(),
+            "CompositeCollector requires at least one collector"
+        );
+        Self { collectors }
+    }
+
+    fn first(&self) -> &(dyn Collector + Send + Sync) {
+        self.collectors[0].as_ref()
+    }
+
+    fn first_mut(&mut self) -> &mut (dyn Collector + Send + Sync) {
+        self.collectors[0].as_mut()
+    }
+}
+
+#[async_trait::async_trait]
+impl Collector for CompositeCollector {
+    fn get_version(&self) -> String {
+        self.first().get_version()
+    }
+
+    fn get_git_hash(&self) -> String {
+        self.first().get_git_hash()
+    }
+
+    fn get_os(&self) -> String {
+        self.first().get_os()
+    }
+
+    fn get_arch(&self) -> String {
+        self.first().get_arch()
+    }
+
+    fn get_compaction_strategy(&self) -> String {
+        self.first().get_compaction_strategy()
+    }
+
+    fn get_wal_sync_policy(&self) -> String {
+        self.first().get_wal_sync_policy()
+    }
+
+    fn get_extra_metadata(&self) -> BTreeMap<String, String> {
+        self.first().get_extra_metadata()
+    }
+
+    fn get_mode(&self) -> Mode {
+        self.first().get_mode()
+    }
+
+    fn get_retry(&self) -> i32 {
+        self.first().get_retry()
+    }
+
+    fn inc_retry(&mut self) {
+        self.first_mut().inc_retry()
+    }
+
+    fn reset_retry(&mut self) {
+        self.first_mut().reset_retry()
+    }
+
+    fn get_last_retry_time(&self) -> Option<Instant> {
+        self.first().get_last_retry_time()
+    }
+
+    fn set_uuid_cache(&mut self, uuid: String) {
+        self.first_mut().set_uuid_cache(uuid)
+    }
+
+    fn get_uuid_cache(&self) -> Option<String> {
+        self.first().get_uuid_cache()
+    }
+
+    async fn get_nodes(&self) -> Option<i32> {
+        let mut total = None;
+        for collector in &self.collectors {
+            if let Some(nodes) = collector.get_nodes().await {
+                total = Some(total.unwrap_or(0) + nodes);
+            }
+        }
+        total
+    }
+
+    async fn get_max_region_bytes(&self) -> Option<i64> {
+        let mut max = None;
+        for collector in &self.collectors {
+            max = max.max(collector.get_max_region_bytes().await);
+        }
+        max
+    }
+
+    async fn get_regions(&self) -> Option<i64> {
+        let mut total = None;
+        for collector in &self.collectors {
+            if let Some(regions) = collector.get_regions().await {
+                total = Some(total.unwrap_or(0) + regions);
+            }
+        }
+        total
+    }
+
+    async fn get_tables(&self) -> Option<i64> {
+        let mut total = None;
+        for collector in &self.collectors {
+            if let Some(tables) = collector.get_tables().await {
+                total = Some(total.unwrap_or(0) + tables);
+            }
+        }
+        total
+    }
+
+    async fn get_workload_stats(&self) -> WorkloadStats {
+        let mut total = WorkloadStats::default();
+        for collector in &self.collectors {
+            let stats = collector.get_workload_stats().await;
+            total.max_region_bytes = total.max_region_bytes.max(stats.max_region_bytes);
+            if let Some(regions) = stats.regions {
+                total.regions = Some(total.regions.unwrap_or(0) + regions);
+            }
+            if let Some(tables) = stats.tables {
+                total.tables = Some(total.tables.unwrap_or(0) + tables);
+            }
+        }
+        total
+    }
+
+    fn get_uuid(&mut self) -> Option<String> {
+        self.first_mut().get_uuid()
+    }
+}
+
+/// Returns the delay before retrying to get the uuid after `retry` consecutive failures.
+fn uuid_retry_delay(retry: i32) -> Duration {
+    if retry <= 0 {
+        return Duration::ZERO;
+    }
+    let index = (retry as usize - 1).min(UUID_RETRY_BACKOFF.len() - 1);
+    UUID_RETRY_BACKOFF[index]
+}
+
+/// Returns the cached uuid of the `collector`, or gets it by `get_uuid` unless
+/// the last failure is too recent.
+fn get_uuid_with_backoff<C, F>(collector: &mut C, get_uuid: F) -> Option<String>
+where
+    C: Collector + ?Sized,
+    F: FnOnce() -> Option<String>,
+{
+    if let Some(uuid) = collector.get_uuid_cache() {
+        return Some(uuid);
+    }
+
+    if let Some(last_retry_time) = collector.get_last_retry_time() {
+        if last_retry_time.elapsed() < uuid_retry_delay(collector.get_retry()) {
+            return None;
+        }
+    }
+
+    match get_uuid() {
+        Some(uuid) => {
+            collector.set_uuid_cache(uuid.clone());
+            collector.reset_retry();
+            Some(uuid)
+        }
+        None => {
+            collector.inc_retry();
+            None
+        }
+    }
+}
+
+fn print_anonymous_usage_data_disclaimer() {
+    info!("Attention: GreptimeDB now collects anonymous usage data to help improve its roadmap and prioritize features.");
+    info!("To learn more about this anonymous program and how to deactivate it if you don't want to participate, please visit the following URL: ");
+    info!("https://docs.greptime.com/reference/telemetry");
+}
+
+/// Returns the path to the local installation uuid file.
+///
+/// The file is stored in the platform data directory, e.g. `$XDG_DATA_HOME/greptimedb`
+/// on Linux, so it survives reboots. Falls back to the temp dir if the data directory
+/// is unavailable or unwritable.
+pub fn uuid_file_path() -> PathBuf {
+    resolve_uuid_path(
+        dirs::data_dir().map(|dir| dir.join(DATA_DIR_NAME)),
+        &env::temp_dir(),
+    )
+}
+
+fn resolve_uuid_path(data_dir: Option<PathBuf>, temp_dir: &Path) -> PathBuf {
+    if let Some(dir) = data_dir {
+        if std::fs::create_dir_all(&dir).is_ok() && is_dir_writable(&dir) {
+            return dir.join(UUID_FILE_NAME);
+        }
+        debug!("Data directory {:?} is not writable, use temp dir", dir);
+    }
+
+    temp_dir.join(UUID_FILE_NAME)
+}
+
+/// Returns whether a file can be created in `dir`.
+///
+/// The permission bits alone don't tell, e.g. for a read-only mount or a
+/// directory owned by another user.
+fn is_dir_writable(dir: &Path) -> bool {
+    let probe = dir.join(".greptimedb-write-probe");
+    match std::fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Reads the local installation uuid, creates a new one if the uuid file doesn't exist.
+///
+/// The uuid file is rewritten with a new uuid if its content isn't a valid uuid, e.g.
+/// it is truncated by an unclean shutdown.
+///
+/// A uuid stored in the temp dir by previous versions is migrated to [uuid_file_path()].
+///
+/// Returns `None` if the uuid can't be persisted, so the caller retries later instead
+/// of reporting a different uuid each time.
+pub fn default_get_uuid() -> Option<String> {
+    let path = uuid_file_path();
+    debug!("Telemetry uuid file: {:?}", path);
+    get_or_create_uuid(&path, &env::temp_dir().join(UUID_FILE_NAME))
+}
+
+fn get_or_create_uuid(path: &Path, legacy_path: &Path) -> Option<String> {
+    match std::fs::read(path) {
+        Ok(bytes) => match uuid::Uuid::parse_str(String::from_utf8_lossy(&bytes).trim()) {
+            Ok(uuid) => Some(uuid.to_string()),
+            Err(e) => {
+                warn!(
+                    "Invalid telemetry uuid in {:?}, regenerate it, error: {}",
+                    path, e
+                );
+                write_uuid(path, uuid::Uuid::new_v4().to_string())
+            }
+        },
+        Err(e) => {
+            if e.kind() != ErrorKind::NotFound {
+                warn!(
+                    "Failed to read telemetry uuid from {:?}, error: {}",
+                    path, e
+                );
+                return None;
+            }
+            let Some(legacy_uuid) = read_legacy_uuid(legacy_path) else {
+                return write_uuid(path, uuid::Uuid::new_v4().to_string());
+            };
+            info!(
+                "Migrate telemetry uuid from {:?} to {:?}",
+                legacy_path, path
+            );
+            let uuid = write_uuid(path, legacy_uuid)?;
+            if legacy_path != path {
+                if let Err(e) = std::fs::remove_file(legacy_path) {
+                    debug!(
+                        "Failed to remove legacy telemetry uuid {:?}: {}",
+                        legacy_path, e
+                    );
+                }
+            }
+            Some(uuid)
+        }
+    }
+}
+
+/// Reads the uuid stored by previous versions, `None` if it is missing or invalid.
+fn read_legacy_uuid(legacy_path: &Path) -> Option<String> {
+    let bytes = std::fs::read(legacy_path).ok()?;
+    match uuid::Uuid::parse_str(String::from_utf8_lossy(&bytes).trim()) {
+        Ok(uuid) => Some(uuid.to_string()),
+        Err(e) => {
+            debug!(
+                "Ignore invalid legacy telemetry uuid in {:?}: {}",
+                legacy_path, e
+            );
+            None
+        }
+    }
+}
+
+/// Persists `uuid` to `path`, returns `None` if failed.
+fn write_uuid(path: &Path, uuid: String) -> Option<String> {
+    match std::fs::write(path, uuid.as_bytes()) {
+        Ok(()) => Some(uuid),
+        Err(e) => {
+            warn!("Failed to write telemetry uuid to {:?}, error: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Reports version info to GreptimeDB.
+///
+/// We do not collect any identity-sensitive information. This task is scheduled
+/// to run every 30 minutes. Data is only sent if the user has explicitly enabled
+/// telemetry, see [TelemetryState].
+pub struct GreptimeDBTelemetry {
+    statistics: Box<dyn Collector + Send + Sync>,
+    client: Option<Client>,
+    telemetry_url: String,
+    /// Whether to gzip the request body.
+    gzip: bool,
+    /// Path to the file persisting the [TelemetryState].
+    ///
+    /// Resolved on the first report if not set, as resolving the default path
+    /// creates the data directory.
+    state_path: Option<PathBuf>,
+    /// Unix timestamp in milliseconds of the last successful report, 0 if none.
+    last_report_unix_ms: Arc<AtomicU64>,
+    /// Whether the disclaimer has been printed, it is printed before the first
+    /// report once the user has enabled telemetry.
+    disclaimer_printed: bool,
+}
+
+#[async_trait::async_trait]
+impl TaskFunction<Error> for GreptimeDBTelemetry {
+    fn name(&self) -> &str {
+        "Greptimedb-telemetry-task"
+    }
+
+    async fn call(&mut self) -> Result<()> {
+        let _ = self.report_telemetry_info().await;
+        Ok(())
+    }
+}
+
+/// [GreptimeDBTelemetry] shared by the repeated task and [GreptimeDBTelemetryTask::report_now()].
+struct SharedGreptimeDBTelemetry(Arc<Mutex<GreptimeDBTelemetry>>);
+
+#[async_trait::async_trait]
+impl TaskFunction<Error> for SharedGreptimeDBTelemetry {
+    fn name(&self) -> &str {
+        "Greptimedb-telemetry-task"
+    }
+
+    async fn call(&mut self) -> Result<()> {
+        self.0.lock().await.call().await
+    }
+}
+
+impl GreptimeDBTelemetry {
+    /// Returns a telemetry task reporting to [TELEMETRY_URL].
+    pub fn new(statistics: Box<dyn Collector + Send + Sync>) -> Self {
+        Self::with_url(statistics, TELEMETRY_URL.to_string())
+    }
+
+    /// Returns a telemetry task reporting to `url`.
+    pub fn with_url(statistics: Box<dyn Collector + Send + Sync>, url: String) -> Self {
+        Self::with_proxy(statistics, url, None)
+    }
+
+    /// Returns a telemetry task reporting to `url` through `proxy`.
+    ///
+    /// If `proxy` is `None`, the proxies from the `HTTP_PROXY`, `HTTPS_PROXY` and
+    /// `NO_PROXY` environment variables are used.
+    pub fn with_proxy(
+        statistics: Box<dyn Collector + Send + Sync>,
+        url: String,
+        proxy: Option<Proxy>,
+    ) -> Self {
+        Self::build(statistics, url, proxy, TelemetryClientConfig::default())
+    }
+
+    /// Returns a telemetry task reporting to `url` through `proxy`, with the HTTP
+    /// client options in `config`.
+    ///
+    /// Returns an error if `config` is invalid, see [TelemetryClientConfig::validate()],
+    /// or the HTTP client can't be built.
+    pub fn with_client_config(
+        statistics: Box<dyn Collector + Send + Sync>,
+        url: String,
+        proxy: Option<Proxy>,
+        config: TelemetryClientConfig,
+    ) -> error::Result<Self> {
+        config.validate()?;
+        let client = new_client(proxy, config).context(error::BuildHttpClientSnafu)?;
+        Ok(Self::with_client(statistics, client, url))
+    }
+
+    /// Returns a telemetry task reporting to `url` by `client`.
+    ///
+    /// The `client` is used as is, so its timeouts and proxies apply to the reports.
+    pub fn with_client(
+        statistics: Box<dyn Collector + Send + Sync>,
+        client: Client,
+        url: String,
+    ) -> Self {
+        Self {
+            statistics,
+            client: Some(client),
+            telemetry_url: url,
+            gzip: false,
+            state_path: None,
+            last_report_unix_ms: Arc::new(AtomicU64::new(0)),
+            disclaimer_printed: false,
+        }
+    }
+
+    fn build(
+        statistics: Box<dyn Collector + Send + Sync>,
+        url: String,
+        proxy: Option<Proxy>,
+        config: TelemetryClientConfig,
+    ) -> Self {
+        let client = new_client(proxy, config)
+            .map_err(|e| warn!("Failed to build telemetry HTTP client: {}", e))
+            .ok();
+        Self {
+            statistics,
+            client,
+            telemetry_url: url,
+            gzip: false,
+            state_path: None,
+            last_report_unix_ms: Arc::new(AtomicU64::new(0)),
+            disclaimer_printed: false,
+        }
+    }
+
+    /// Sets the path to the file persisting the [TelemetryState], a file in the
+    /// directory of [uuid_file_path()] by default.
+    pub fn with_state_path(mut self, path: PathBuf) -> Self {
+        self.state_path = Some(path);
+        self
+    }
+
+    /// Sets whether to gzip the request body, disabled by default.
+    ///
+    /// The endpoint must accept `Content-Encoding: gzip` requests.
+    pub fn with_gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// Returns the time of the last report that got a successful response.
+    pub fn last_successful_report(&self) -> Option<SystemTime> {
+        unix_ms_to_time(&self.last_report_unix_ms)
+    }
+
+    pub async fn report_telemetry_info(&mut self) -> Option<Response> {
+        let state_path = self.state_path.get_or_insert_with(default_state_path);
+        let state = read_telemetry_state(state_path);
+        if state != TelemetryState::Enabled {
+            debug!(
+                "Skip reporting telemetry data, telemetry state: {:?}",
+                state
+            );
+            return None;
+        }
+        if !self.disclaimer_printed {
+            print_anonymous_usage_data_disclaimer();
+            self.disclaimer_printed = true;
+        }
+
+        match self.statistics.get_uuid() {
+            Some(uuid) => {
+                let workload = self.statistics.get_workload_stats().await;
+                let data = StatisticData {
+                    os: self.statistics.get_os(),
+                    version: self.statistics.get_version(),
+                    git_commit: self.statistics.get_git_hash(),
+                    arch: self.statistics.get_arch(),
+                    mode: self.statistics.get_mode(),
+                    nodes: self.statistics.get_nodes().await,
+                    max_region_bytes: workload.max_region_bytes,
+                    regions: workload.regions,
+                    tables: workload.tables,
+                    compaction_strategy: self.statistics.get_compaction_strategy(),
+                    wal_sync_policy: self.statistics.get_wal_sync_policy(),
+                    extra: Some(self.statistics.get_extra_metadata())
+                        .filter(|extra| !extra.is_empty()),
+                    uuid,
+                };
+
+                if let Some(client) = self.client.as_ref() {
+                    info!("reporting greptimedb version: {:?}", data);
+                    // Counts the attempt first so encode failures are included.
+                    metrics::increment_counter!(METRIC_TELEMETRY_REPORTS_TOTAL);
+                    let request = client.post(&self.telemetry_url);
+                    let request = if self.gzip {
+                        match gzip_json(&data) {
+                            Ok(body) => request
+                                .header(CONTENT_TYPE, "application/json")
+                                .header(CONTENT_ENCODING, "gzip")
+                                .body(body),
+                            Err(e) => {
+                                debug!("Failed to compress telemetry data: {}", e);
+                                metrics::increment_counter!(
+                                    METRIC_TELEMETRY_REPORT_FAILURES_TOTAL,
+                                    METRIC_TELEMETRY_FAILURE_REASON => "encode"
+                                );
+                                return None;
+                            }
+                        }
+                    } else {
+                        request.json(&data)
+                    };
+                    let result = request.send().await;
+                    debug!("report version result: {:?}", result);
+                    match &result {
+                        Ok(response) if !response.status().is_success() => {
+                            metrics::increment_counter!(
+                                METRIC_TELEMETRY_REPORT_FAILURES_TOTAL,
+                                METRIC_TELEMETRY_FAILURE_REASON => "status"
+                            );
+                        }
+                        Ok(_) => {
+                            let now = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map(|elapsed| elapsed.as_millis() as u64)
+                                .unwrap_or_default();
+                            self.last_report_unix_ms.store(now, Ordering::Relaxed);
+                        }
+                        Err(_) => {
+                            metrics::increment_counter!(
+                                METRIC_TELEMETRY_REPORT_FAILURES_TOTAL,
+                                METRIC_TELEMETRY_FAILURE_REASON => "network"
+                            );
+                        }
+                    }
+                    result.ok()
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+fn new_client(proxy: Option<Proxy>, config: TelemetryClientConfig) -> reqwest::Result<Client> {
+    let mut builder = Client::builder()
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.timeout);
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(proxy);
+    }
+    builder.build()
+}
+
+/// Returns `data` serialized to JSON and compressed by gzip.
+fn gzip_json(data: &StatisticData) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, data)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::io::Read;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use common_error::ext::ErrorExt;
+    use common_test_util::temp_dir::create_temp_dir;
+    use flate2::read::GzDecoder;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server, StatusCode};
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    /// Serializes tests reading or changing the [TELEMETRY_DISABLED_ENV] variable.
+    static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// Starts a server echoing the request body back, counting the requests it receives.
+    fn start_mock_server(counter: Arc<AtomicUsize>) -> (SocketAddr, oneshot::Sender<()>) {
+        let make_svc = make_service_fn(move |_conn| {
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let _ = counter.fetch_add(1, Ordering::Relaxed);
+                    async move { Ok::<_, Infallible>(Response::new(req.into_body())) }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_svc);
+        let addr = server.local_addr();
+        let (tx, rx) = oneshot::channel::<()>();
+        let graceful = server.with_graceful_shutdown(async move {
+            let _ = rx.await;
+        });
+        let _handle = tokio::spawn(graceful);
+
+        (addr, tx)
+    }
+
+    /// Starts a server responding to all requests with an internal error.
+    fn start_error_server(counter: Arc<AtomicUsize>) -> (SocketAddr, oneshot::Sender<()>) {
+        let make_svc = make_service_fn(move |_conn| {
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |_req: Request<Body>| {
+                    let _ = counter.fetch_add(1, Ordering::Relaxed);
+                    async move {
+                        let mut response = Response::new(Body::empty());
+                        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_svc);
+        let addr = server.local_addr();
+        let (tx, rx) = oneshot::channel::<()>();
+        let graceful = server.with_graceful_shutdown(async move {
+            let _ = rx.await;
+        });
+        let _handle = tokio::spawn(graceful);
+
+        (addr, tx)
+    }
+
+    /// Starts a server decoding gzip request bodies and responding with the decoded body.
+    ///
+    /// Requests without `Content-Encoding: gzip` are rejected.
+    fn start_gzip_server(counter: Arc<AtomicUsize>) -> (SocketAddr, oneshot::Sender<()>) {
+        let make_svc = make_service_fn(move |_conn| {
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let _ = counter.fetch_add(1, Ordering::Relaxed);
+                    async move {
+                        let gzip = req
+                            .headers()
+                            .get(CONTENT_ENCODING)
+                            .map(|value| value == "gzip")
+                            .unwrap_or(false);
+                        let mut response = Response::new(Body::empty());
+                        if !gzip {
+                            *response.status_mut() = StatusCode::BAD_REQUEST;
+                            return Ok::<_, Infallible>(response);
+                        }
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        let mut decoded = Vec::new();
+                        let _ = GzDecoder::new(&body[..]).read_to_end(&mut decoded).unwrap();
+                        *response.body_mut() = Body::from(decoded);
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_svc);
+        let addr = server.local_addr();
+        let (tx, rx) = oneshot::channel::<()>();
+        let graceful = server.with_graceful_shutdown(async move {
+            let _ = rx.await;
+        });
+        let _handle = tokio::spawn(graceful);
+
+        (addr, tx)
+    }
+
+    struct TestStatistic;
+
+    #[async_trait::async_trait]
+    impl Collector for TestStatistic {
+        fn get_mode(&self) -> Mode {
+            Mode::Standalone
+        }
+
+        async fn get_nodes(&self) -> Option<i32> {
+            Some(1)
+        }
+
+        async fn get_max_region_bytes(&self) -> Option<i64> {
+            Some(1024)
+        }
+
+        fn get_wal_sync_policy(&self) -> String {
+            "sync_write".to_string()
+        }
+
+        fn get_retry(&self) -> i32 {
+            unimplemented!()
+        }
+
+        fn inc_retry(&mut self) {
+            unimplemented!()
+        }
+
+        fn reset_retry(&mut self) {
+            unimplemented!()
+        }
+
+        fn get_last_retry_time(&self) -> Option<Instant> {
+            unimplemented!()
+        }
+
+        fn set_uuid_cache(&mut self, _: String) {
+            unimplemented!()
+        }
+
+        fn get_uuid_cache(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        fn get_uuid(&mut self) -> Option<String> {
+            Some("test".to_string())
+        }
+    }
+
+    #[test]
+    fn test_telemetry_state_persistence() {
+        let dir = create_temp_dir("telemetry-state");
+        let path = dir.path().join(STATE_FILE_NAME);
+
+        // No choice made yet.
+        assert_eq!(TelemetryState::Undecided, read_telemetry_state(&path));
+
+        for state in [
+            TelemetryState::Enabled,
+            TelemetryState::Disabled,
+            TelemetryState::Undecided,
+        ] {
+            write_telemetry_state(&path, state).unwrap();
+            assert_eq!(state, read_telemetry_state(&path));
+        }
+
+        // Unknown content is treated as undecided.
+        std::fs::write(&path, b"garbage").unwrap();
+        assert_eq!(TelemetryState::Undecided, read_telemetry_state(&path));
+    }
+
+    #[tokio::test]
+    async fn test_greptimedb_telemetry() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry");
+
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(TestStatistic), format!("http://{addr}"));
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+
+        // Undecided and disabled states suppress reporting and the disclaimer.
+        assert!(report.report_telemetry_info().await.is_none());
+        write_telemetry_state(&state_path, TelemetryState::Disabled).unwrap();
+        assert!(report.report_telemetry_info().await.is_none());
+        assert_eq!(0, counter.load(Ordering::Relaxed));
+        assert!(!report.disclaimer_printed);
+
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+        let response = report.report_telemetry_info().await.unwrap();
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+        assert!(report.disclaimer_printed);
+
+        let body = response.json::<StatisticData>().await.unwrap();
+        assert_eq!(env::consts::ARCH, body.arch);
+        assert_eq!(env::consts::OS, body.os);
+        assert_eq!(env!("CARGO_PKG_VERSION"), body.version);
+        assert_eq!(env!("GIT_COMMIT"), body.git_commit);
+        assert_eq!(Mode::Standalone, body.mode);
+        assert_eq!(Some(1), body.nodes);
+        assert_eq!(Some(1024), body.max_region_bytes);
+        assert_eq!(UNKNOWN_CONFIG_LABEL, body.compaction_strategy);
+        assert_eq!("sync_write", body.wal_sync_policy);
+        assert!(body.extra.is_none());
+        assert!(body.regions.is_none());
+        assert!(body.tables.is_none());
+        assert_eq!("test", body.uuid);
+
+        let _ = tx.send(());
+    }
+
+    /// A collector whose uuid is obtained by the test.
+    #[derive(Default)]
+    struct RetryStatistic {
+        uuid: Option<String>,
+        retry: i32,
+        last_retry_time: Option<Instant>,
+    }
+
+    #[async_trait::async_trait]
+    impl Collector for RetryStatistic {
+        fn get_mode(&self) -> Mode {
+            Mode::Standalone
+        }
+
+        async fn get_nodes(&self) -> Option<i32> {
+            None
+        }
+
+        fn get_retry(&self) -> i32 {
+            self.retry
+        }
+
+        fn inc_retry(&mut self) {
+            self.retry += 1;
+            self.last_retry_time = Some(Instant::now());
+        }
+
+        fn reset_retry(&mut self) {
+            self.retry = 0;
+            self.last_retry_time = None;
+        }
+
+        fn get_last_retry_time(&self) -> Option<Instant> {
+            self.last_retry_time
+        }
+
+        fn set_uuid_cache(&mut self, uuid: String) {
+            self.uuid = Some(uuid);
+        }
+
+        fn get_uuid_cache(&self) -> Option<String> {
+            self.uuid.clone()
+        }
+    }
+
+    #[test]
+    fn test_uuid_retry_delay() {
+        assert_eq!(Duration::ZERO, uuid_retry_delay(0));
+        assert_eq!(Duration::from_secs(60), uuid_retry_delay(1));
+        assert_eq!(Duration::from_secs(5 * 60), uuid_retry_delay(2));
+        assert_eq!(Duration::from_secs(30 * 60), uuid_retry_delay(3));
+        assert_eq!(Duration::from_secs(30 * 60), uuid_retry_delay(100));
+    }
+
+    #[test]
+    fn test_get_uuid_with_backoff() {
+        let mut collector = RetryStatistic::default();
+        let attempts = AtomicUsize::new(0);
+        let fail = || {
+            let _ = attempts.fetch_add(1, Ordering::Relaxed);
+            None
+        };
+
+        assert!(get_uuid_with_backoff(&mut collector, fail).is_none());
+        assert_eq!(1, collector.retry);
+        assert_eq!(1, attempts.load(Ordering::Relaxed));
+
+        // Don't retry before the delay elapses.
+        assert!(get_uuid_with_backoff(&mut collector, fail).is_none());
+        assert_eq!(1, collector.retry);
+        assert_eq!(1, attempts.load(Ordering::Relaxed));
+
+        // Retry after the delay, the next delay is longer.
+        collector.last_retry_time = Instant::now().checked_sub(Duration::from_secs(61));
+        assert!(get_uuid_with_backoff(&mut collector, fail).is_none());
+        assert_eq!(2, collector.retry);
+        assert_eq!(2, attempts.load(Ordering::Relaxed));
+        collector.last_retry_time = Instant::now().checked_sub(Duration::from_secs(61));
+        assert!(get_uuid_with_backoff(&mut collector, fail).is_none());
+        assert_eq!(2, attempts.load(Ordering::Relaxed));
+
+        // Success resets the retry state.
+        collector.last_retry_time = Instant::now().checked_sub(Duration::from_secs(5 * 60 + 1));
+        let uuid = get_uuid_with_backoff(&mut collector, || Some("uuid".to_string()));
+        assert_eq!(Some("uuid".to_string()), uuid);
+        assert_eq!(0, collector.retry);
+        assert!(collector.last_retry_time.is_none());
+
+        // The cached uuid is returned without getting it again.
+        let uuid = get_uuid_with_backoff(&mut collector, fail);
+        assert_eq!(Some("uuid".to_string()), uuid);
+        assert_eq!(2, attempts.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_uuid_migration() {
+        let temp_dir = create_temp_dir("telemetry-uuid-temp");
+        let data_dir = create_temp_dir("telemetry-uuid-data");
+        let legacy_path = temp_dir.path().join(UUID_FILE_NAME);
+        let legacy_uuid = uuid::Uuid::new_v4().to_string();
+        std::fs::write(&legacy_path, legacy_uuid.as_bytes()).unwrap();
+
+        let path = resolve_uuid_path(Some(data_dir.path().join(DATA_DIR_NAME)), temp_dir.path());
+        assert_eq!(
+            data_dir.path().join(DATA_DIR_NAME).join(UUID_FILE_NAME),
+            path
+        );
+
+        // The legacy uuid is migrated and then read from the new path.
+        assert_eq!(
+            legacy_uuid,
+            get_or_create_uuid(&path, &legacy_path).unwrap()
+        );
+        assert_eq!(legacy_uuid.as_bytes(), &std::fs::read(&path).unwrap()[..]);
+        assert!(!legacy_path.exists());
+        assert_eq!(
+            legacy_uuid,
+            get_or_create_uuid(&path, &legacy_path).unwrap()
+        );
+
+        // An invalid legacy uuid is not migrated.
+        std::fs::remove_file(&path).unwrap();
+        std::fs::write(&legacy_path, b"legacy-uuid").unwrap();
+        let uuid = get_or_create_uuid(&path, &legacy_path).unwrap();
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+        assert_eq!(uuid.as_bytes(), &std::fs::read(&path).unwrap()[..]);
+    }
+
+    #[test]
+    fn test_uuid_write_failure() {
+        let dir = create_temp_dir("telemetry-uuid-unwritable");
+        // The uuid can't be written under a missing directory.
+        let path = dir.path().join("missing").join(UUID_FILE_NAME);
+        let legacy_path = dir.path().join(UUID_FILE_NAME);
+        assert_eq!(None, get_or_create_uuid(&path, &legacy_path));
+
+        // The legacy uuid is kept if it can't be migrated.
+        std::fs::write(&legacy_path, uuid::Uuid::new_v4().to_string()).unwrap();
+        assert_eq!(None, get_or_create_uuid(&path, &legacy_path));
+        assert!(legacy_path.exists());
+    }
+
+    #[test]
+    fn test_get_or_create_uuid() {
+        let dir = create_temp_dir("telemetry-uuid");
+        let path = dir.path().join(UUID_FILE_NAME);
+        let legacy_path = dir.path().join("legacy");
+
+        // Missing file.
+        let uuid = get_or_create_uuid(&path, &legacy_path).unwrap();
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+        assert_eq!(uuid.as_bytes(), &std::fs::read(&path).unwrap()[..]);
+
+        // Valid existing uuid.
+        assert_eq!(uuid, get_or_create_uuid(&path, &legacy_path).unwrap());
+
+        // Corrupted uuid is regenerated and rewritten.
+        let corrupted: [&[u8]; 3] = [&uuid.as_bytes()[..10], b"", b"\xff\xfe garbage"];
+        for content in corrupted {
+            std::fs::write(&path, content).unwrap();
+            let new_uuid = get_or_create_uuid(&path, &legacy_path).unwrap();
+            assert!(uuid::Uuid::parse_str(&new_uuid).is_ok());
+            assert_ne!(uuid, new_uuid);
+            assert_eq!(new_uuid.as_bytes(), &std::fs::read(&path).unwrap()[..]);
+            assert_eq!(new_uuid, get_or_create_uuid(&path, &legacy_path).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_uuid_path_fallback() {
+        let temp_dir = create_temp_dir("telemetry-uuid-fallback");
+        // The data directory can't be created under a file.
+        let file = temp_dir.path().join("file");
+        std::fs::write(&file, b"").unwrap();
+
+        let path = resolve_uuid_path(Some(file.join(DATA_DIR_NAME)), temp_dir.path());
+        assert_eq!(temp_dir.path().join(UUID_FILE_NAME), path);
+        // The probe file is removed from a writable data directory.
+        let data_dir = temp_dir.path().join(DATA_DIR_NAME);
+        let path = resolve_uuid_path(Some(data_dir.clone()), temp_dir.path());
+        assert_eq!(data_dir.join(UUID_FILE_NAME), path);
+        assert_eq!(0, std::fs::read_dir(&data_dir).unwrap().count());
+        let path = resolve_uuid_path(None, temp_dir.path());
+        assert_eq!(temp_dir.path().join(UUID_FILE_NAME), path);
+
+        // A new uuid is created if there is no legacy uuid.
+        let uuid = get_or_create_uuid(&path, &path).unwrap();
+        assert_eq!(uuid, get_or_create_uuid(&path, &path).unwrap());
+    }
+
+    #[test]
+    fn test_is_disabled_value() {
+        for value in ["1", "true", "TRUE", " true "] {
+            assert!(is_disabled_value(value), "{value}");
+        }
+        for value in ["", "0", "false", "yes"] {
+            assert!(!is_disabled_value(value), "{value}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_disabled_by_env() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry-env");
+
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(TestStatistic), format!("http://{addr}"));
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        let task = {
+            let _guard = ENV_LOCK.lock().unwrap();
+            env::set_var(TELEMETRY_DISABLED_ENV, "true");
+            let task = GreptimeDBTelemetryTask::enable(Duration::from_millis(10), report);
+            env::remove_var(TELEMETRY_DISABLED_ENV);
+            task
+        };
+        assert!(matches!(task, GreptimeDBTelemetryTask::Disable));
+
+        task.start(common_runtime::bg_runtime()).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(0, counter.load(Ordering::Relaxed));
+        task.stop().await.unwrap();
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_proxy() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        // The mock server acts as a proxy that answers requests itself.
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry-proxy");
+
+        let proxy = Proxy::http(format!("http://{addr}")).unwrap();
+        let mut report = GreptimeDBTelemetry::with_proxy(
+            Box::new(TestStatistic),
+            "http://telemetry.invalid/statistics".to_string(),
+            Some(proxy),
+        );
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        let response = report.report_telemetry_info().await.unwrap();
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+        let body = response.json::<StatisticData>().await.unwrap();
+        assert_eq!("test", body.uuid);
+        let _ = tx.send(());
+
+        // An unreachable proxy fails the report without panicking.
+        let proxy = Proxy::http("http://127.0.0.1:1").unwrap();
+        let mut report = GreptimeDBTelemetry::with_proxy(
+            Box::new(TestStatistic),
+            "http://telemetry.invalid/statistics".to_string(),
+            Some(proxy),
+        );
+        report.state_path = Some(dir.path().join(STATE_FILE_NAME));
+        assert!(report.report_telemetry_info().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_with_client() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry-client");
+
+        // Only the provided client knows how to reach the mock server.
+        let client = Client::builder()
+            .proxy(Proxy::http(format!("http://{addr}")).unwrap())
+            .build()
+            .unwrap();
+        let mut report = GreptimeDBTelemetry::with_client(
+            Box::new(TestStatistic),
+            client,
+            "http://telemetry.invalid/statistics".to_string(),
+        );
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        let response = report.report_telemetry_info().await.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+        let body = response.json::<StatisticData>().await.unwrap();
+        assert_eq!("test", body.uuid);
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_last_successful_report() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry-last-report");
+
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(TestStatistic), format!("http://{addr}"));
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+        assert!(report.last_successful_report().is_none());
+
+        let before = SystemTime::now() - Duration::from_millis(1);
+        assert!(report.report_telemetry_info().await.is_some());
+        let last_report = report.last_successful_report().unwrap();
+        assert!(last_report >= before);
+        assert!(last_report <= SystemTime::now());
+        let _ = tx.send(());
+
+        // Failed reports don't update the timestamp.
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_error_server(counter.clone());
+        report.telemetry_url = format!("http://{addr}");
+        assert!(report.report_telemetry_info().await.is_some());
+        assert_eq!(Some(last_report), report.last_successful_report());
+        let _ = tx.send(());
+
+        report.telemetry_url = "http://127.0.0.1:1".to_string();
+        assert!(report.report_telemetry_info().await.is_none());
+        assert_eq!(Some(last_report), report.last_successful_report());
+    }
+
+    #[test]
+    fn test_extra_metadata_json() {
+        let mut data = StatisticData {
+            os: "linux".to_string(),
+            version: "0.1.0".to_string(),
+            arch: "x86_64".to_string(),
+            mode: Mode::Standalone,
+            git_commit: "abc".to_string(),
+            nodes: Some(1),
+            max_region_bytes: None,
+            regions: None,
+            tables: None,
+            compaction_strategy: UNKNOWN_CONFIG_LABEL.to_string(),
+            wal_sync_policy: UNKNOWN_CONFIG_LABEL.to_string(),
+            extra: None,
+            uuid: "test".to_string(),
+        };
+        let json = serde_json::to_value(&data).unwrap();
+        assert!(json.get("extra").is_none());
+
+        data.extra = Some(BTreeMap::from([
+            ("cloud".to_string(), "aws".to_string()),
+            ("tier".to_string(), "prod".to_string()),
+        ]));
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(
+            serde_json::json!({"cloud": "aws", "tier": "prod"}),
+            json["extra"]
+        );
+    }
+
+    #[test]
+    fn test_workload_scale_json() {
+        let mut data = StatisticData {
+            os: "linux".to_string(),
+            version: "0.1.0".to_string(),
+            arch: "x86_64".to_string(),
+            mode: Mode::Standalone,
+            git_commit: "abc".to_string(),
+            nodes: Some(1),
+            max_region_bytes: None,
+            regions: None,
+            tables: None,
+            compaction_strategy: UNKNOWN_CONFIG_LABEL.to_string(),
+            wal_sync_policy: UNKNOWN_CONFIG_LABEL.to_string(),
+            extra: None,
+            uuid: "test".to_string(),
+        };
+        let json = serde_json::to_value(&data).unwrap();
+        assert!(json.get("max_region_bytes").is_none());
+        assert!(json.get("regions").is_none());
+        assert!(json.get("tables").is_none());
+
+        data.max_region_bytes = Some(1024);
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(1024, json["max_region_bytes"]);
+
+        data.regions = Some(8);
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(8, json["regions"]);
+        assert!(json.get("tables").is_none());
+
+        data.tables = Some(2);
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(8, json["regions"]);
+        assert_eq!(2, json["tables"]);
+    }
+
+    #[tokio::test]
+    async fn test_report_now() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry-report-now");
+
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(TestStatistic), format!("http://{addr}"));
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        let task = {
+            let _guard = ENV_LOCK.lock().unwrap();
+            GreptimeDBTelemetryTask::enable(Duration::from_secs(3600), report)
+        };
+        task.start(common_runtime::bg_runtime()).unwrap();
+        assert!(task.last_successful_report().is_none());
+        task.report_now().await.unwrap();
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+        assert!(task.last_successful_report().is_some());
+        task.stop().await.unwrap();
+        assert!(GreptimeDBTelemetryTask::disable()
+            .last_successful_report()
+            .is_none());
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_report_failure_metrics() {
+        common_telemetry::init_default_metrics_recorder();
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_error_server(counter.clone());
+        let dir = create_temp_dir("telemetry-failure");
+
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(TestStatistic), format!("http://{addr}"));
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        // The recorder is global and other tests may fail reports concurrently, so
+        // only checks that the counter increases.
+        let before = status_failures();
+        let response = report.report_telemetry_info().await.unwrap();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+
+        let text = common_telemetry::metric::try_handle().unwrap().render();
+        assert!(text.contains(METRIC_TELEMETRY_REPORTS_TOTAL), "{text}");
+        let after = status_failures();
+        assert!(after > before, "before: {before}, after: {after}");
+
+        let _ = tx.send(());
+    }
+
+    /// Returns the number of reports failed by the response status.
+    fn status_failures() -> f64 {
+        let prefix = format!("{METRIC_TELEMETRY_REPORT_FAILURES_TOTAL}{{reason=\"status\"}} ");
+        common_telemetry::metric::try_handle()
+            .unwrap()
+            .render()
+            .lines()
+            .find_map(|line| line.strip_prefix(&prefix))
+            .map(|value| value.trim().parse().unwrap())
+            .unwrap_or(0.0)
+    }
+
+    #[test]
+    fn test_telemetry_jitter() {
+        let interval = Duration::from_secs(1800);
+        let _guard = ENV_LOCK.lock().unwrap();
+        for (jitter, expect) in [
+            (0.0, 0.0),
+            (0.5, 0.5),
+            (f64::NAN, DEFAULT_TELEMETRY_JITTER),
+            (1.0, DEFAULT_TELEMETRY_JITTER),
+            (-0.1, DEFAULT_TELEMETRY_JITTER),
+        ] {
+            let task = GreptimeDBTelemetryTask::enable_with_jitter(
+                interval,
+                jitter,
+                GreptimeDBTelemetry::new(Box::new(TestStatistic)),
+            );
+            match task {
+                GreptimeDBTelemetryTask::Enable { task, .. } => {
+                    assert_eq!(interval, task.interval());
+                    assert_eq!(expect, task.jitter(), "{jitter}");
+                }
+                GreptimeDBTelemetryTask::Disable => unreachable!(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_telemetry_url() {
+        let default_report = GreptimeDBTelemetry::new(Box::new(TestStatistic));
+        assert_eq!(TELEMETRY_URL, default_report.telemetry_url);
+        // The state path is resolved on the first report.
+        assert!(default_report.state_path.is_none());
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry-url");
+
+        let url = format!("http://{addr}/internal/statistics");
+        let mut report = GreptimeDBTelemetry::with_url(Box::new(TestStatistic), url.clone());
+        assert_eq!(url, report.telemetry_url);
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        let response = report.report_telemetry_info().await.unwrap();
+        assert_eq!(url, response.url().as_str());
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_gzip_report() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_gzip_server(counter.clone());
+        let dir = create_temp_dir("telemetry-gzip");
+
+        // The server rejects uncompressed reports.
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(TestStatistic), format!("http://{addr}"));
+        assert!(!report.gzip);
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+        let response = report.report_telemetry_info().await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(TestStatistic), format!("http://{addr}"))
+                .with_gzip(true);
+        report.state_path = Some(dir.path().join(STATE_FILE_NAME));
+        let response = report.report_telemetry_info().await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(2, counter.load(Ordering::Relaxed));
+
+        let body = response.json::<StatisticData>().await.unwrap();
+        assert_eq!(env::consts::ARCH, body.arch);
+        assert_eq!(env::consts::OS, body.os);
+        assert_eq!(env!("CARGO_PKG_VERSION"), body.version);
+        assert_eq!(Mode::Standalone, body.mode);
+        assert_eq!(Some(1), body.nodes);
+        assert_eq!(Some(1024), body.max_region_bytes);
+        assert_eq!("sync_write", body.wal_sync_policy);
+        assert_eq!("test", body.uuid);
+
+        let _ = tx.send(());
+    }
+
+    /// A collector reporting a fixed number of nodes.
+    struct NodesStatistic {
+        nodes: Option<i32>,
+    }
+
+    #[async_trait::async_trait]
+    impl Collector for NodesStatistic {
+        fn get_mode(&self) -> Mode {
+            Mode::Distributed
+        }
+
+        async fn get_nodes(&self) -> Option<i32> {
+            self.nodes
+        }
+
+        async fn get_max_region_bytes(&self) -> Option<i64> {
+            self.nodes.map(|nodes| nodes as i64 * 1024)
+        }
+
+        async fn get_regions(&self) -> Option<i64> {
+            self.nodes.map(|nodes| nodes as i64 * 10)
+        }
+
+        async fn get_tables(&self) -> Option<i64> {
+            self.nodes.map(|nodes| nodes as i64 * 2)
+        }
+
+        fn get_retry(&self) -> i32 {
+            unimplemented!()
+        }
+
+        fn inc_retry(&mut self) {
+            unimplemented!()
+        }
+
+        fn reset_retry(&mut self) {
+            unimplemented!()
+        }
+
+        fn get_last_retry_time(&self) -> Option<Instant> {
+            unimplemented!()
+        }
+
+        fn set_uuid_cache(&mut self, _: String) {
+            unimplemented!()
+        }
+
+        fn get_uuid_cache(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        fn get_uuid(&mut self) -> Option<String> {
+            Some(format!("{:?}", self.nodes))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_composite_collector() {
+        let mut collector = CompositeCollector::new(vec![
+            Box::new(NodesStatistic { nodes: Some(3) }),
+            Box::new(NodesStatistic { nodes: None }),
+            Box::new(NodesStatistic { nodes: Some(2) }),
+        ]);
+        assert_eq!(Some(5), collector.get_nodes().await);
+        assert_eq!(Some(3 * 1024), collector.get_max_region_bytes().await);
+        assert_eq!(Some(50), collector.get_regions().await);
+        assert_eq!(Some(10), collector.get_tables().await);
+        assert_eq!(
+            WorkloadStats {
+                max_region_bytes: Some(3 * 1024),
+                regions: Some(50),
+                tables: Some(10),
+            },
+            collector.get_workload_stats().await
+        );
+        // Delegated to the first collector.
+        assert_eq!(Some("Some(3)".to_string()), collector.get_uuid());
+
+        let collector = CompositeCollector::new(vec![
+            Box::new(NodesStatistic { nodes: None }),
+            Box::new(NodesStatistic { nodes: None }),
+        ]);
+        assert_eq!(None, collector.get_nodes().await);
+        assert_eq!(None, collector.get_max_region_bytes().await);
+        assert_eq!(None, collector.get_regions().await);
+        assert_eq!(None, collector.get_tables().await);
+        assert_eq!(
+            WorkloadStats::default(),
+            collector.get_workload_stats().await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_composite_collector_report() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server(counter.clone());
+        let dir = create_temp_dir("telemetry-composite");
+
+        let collector = CompositeCollector::new(vec![
+            Box::new(TestStatistic),
+            Box::new(NodesStatistic { nodes: Some(2) }),
+        ]);
+        let mut report =
+            GreptimeDBTelemetry::with_url(Box::new(collector), format!("http://{addr}"));
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        let response = report.report_telemetry_info().await.unwrap();
+        let body = response.json::<StatisticData>().await.unwrap();
+        assert_eq!(Mode::Standalone, body.mode);
+        assert_eq!(Some(3), body.nodes);
+        assert_eq!(Some(2048), body.max_region_bytes);
+        assert_eq!(Some(20), body.regions);
+        assert_eq!(Some(4), body.tables);
+        assert_eq!("test", body.uuid);
+
+        let _ = tx.send(());
+    }
+
+    #[test]
+    fn test_client_config_validate() {
+        assert!(TelemetryClientConfig::default().validate().is_ok());
+
+        let config = TelemetryClientConfig {
+            connect_timeout: Duration::ZERO,
+            ..Default::default()
+        };
+        let err = GreptimeDBTelemetry::with_client_config(
+            Box::new(TestStatistic),
+            TELEMETRY_URL.to_string(),
+            None,
+            config,
+        )
+        .err()
+        .unwrap();
+        assert!(
+            matches!(err, error::Error::InvalidClientConfig { .. }),
+            "{err}"
+        );
+        assert!(err.to_string().contains("connect_timeout"), "{err}");
+        assert_eq!(
+            common_error::status_code::StatusCode::InvalidArguments,
+            err.status_code()
+        );
+
+        let config = TelemetryClientConfig {
+            timeout: Duration::ZERO,
+            ..Default::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("timeout"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_client_timeout() {
+        // A server accepting connections but never responding.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let dir = create_temp_dir("telemetry-timeout");
+
+        let config = TelemetryClientConfig {
+            connect_timeout: Duration::from_millis(1),
+            timeout: Duration::from_millis(1),
+        };
+        let mut report = GreptimeDBTelemetry::with_client_config(
+            Box::new(TestStatistic),
+            format!("http://{addr}"),
+            None,
+            config,
+        )
+        .unwrap();
+        let state_path = dir.path().join(STATE_FILE_NAME);
+        report.state_path = Some(state_path.clone());
+        write_telemetry_state(&state_path, TelemetryState::Enabled).unwrap();
+
+        assert!(report.report_telemetry_info().await.is_none());
+        drop(listener);
+    }
+}
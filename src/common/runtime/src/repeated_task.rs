@@ -18,6 +18,7 @@ use std::time::Duration;
 
 use common_error::ext::ErrorExt;
 use common_telemetry::logging;
+use rand::Rng;
 use snafu::{ensure, ResultExt};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
@@ -37,6 +38,9 @@ pub trait TaskFunction<E> {
 
 pub type BoxedTaskFunction<E> = Box<dyn TaskFunction<E> + Send + Sync + 'static>;
 
+/// Max fraction of the interval to jitter by.
+const MAX_JITTER: f64 = 0.99;
+
 struct TaskInner<E> {
     /// The repeated task handle. This handle is Some if the task is started.
     task_handle: Option<JoinHandle<()>>,
@@ -50,6 +54,8 @@ pub struct RepeatedTask<E> {
     inner: Mutex<TaskInner<E>>,
     started: AtomicBool,
     interval: Duration,
+    /// Max fraction of the interval to randomly add or subtract before each run.
+    jitter: f64,
 }
 
 impl<E> std::fmt::Display for RepeatedTask<E> {
@@ -85,9 +91,34 @@ impl<E: ErrorExt + 'static> RepeatedTask<E> {
             }),
             started: AtomicBool::new(false),
             interval,
+            jitter: 0.0,
         }
     }
 
+    /// Randomly increases or decreases the delay before each run by up to
+    /// `jitter` of the interval, so tasks started together don't run at the same
+    /// time. The delay is drawn again for every run.
+    ///
+    /// `jitter` is clamped to `[0.0, 0.99]`, NaN means no jitter.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = if jitter.is_nan() {
+            0.0
+        } else {
+            jitter.clamp(0.0, MAX_JITTER)
+        };
+        self
+    }
+
+    /// Returns the interval between two runs of the task, before jittering.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the max fraction of the interval to jitter by.
+    pub fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
     pub fn started(&self) -> bool {
         self.started.load(Ordering::Relaxed)
     }
@@ -100,6 +131,7 @@ impl<E: ErrorExt + 'static> RepeatedTask<E> {
         );
 
         let interval = self.interval;
+        let jitter = self.jitter;
         let child = self.cancel_token.child_token();
         // Safety: The task is not started.
         let mut task_fn = inner.task_fn.take().unwrap();
@@ -107,7 +139,7 @@ impl<E: ErrorExt + 'static> RepeatedTask<E> {
         let handle = runtime.spawn(async move {
             loop {
                 tokio::select! {
-                    _ = tokio::time::sleep(interval) => {}
+                    _ = tokio::time::sleep(jittered(interval, jitter)) => {}
                     _ = child.cancelled() => {
                         return;
                     }
@@ -153,6 +185,14 @@ impl<E: ErrorExt + 'static> RepeatedTask<E> {
     }
 }
 
+/// Returns `interval` randomly increased or decreased by up to `jitter` of it.
+fn jittered(interval: Duration, jitter: f64) -> Duration {
+    if jitter == 0.0 {
+        return interval;
+    }
+    interval.mul_f64(1.0 + rand::thread_rng().gen_range(-jitter..=jitter))
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::AtomicI32;
@@ -192,4 +232,33 @@ mod tests {
 
         assert_eq!(n.load(Ordering::Relaxed), 5);
     }
+
+    #[test]
+    fn test_jittered() {
+        let interval = Duration::from_secs(1800);
+        assert_eq!(interval, jittered(interval, 0.0));
+
+        let intervals: Vec<_> = (0..100).map(|_| jittered(interval, 0.1)).collect();
+        // Within 1800s +/- 10%, allowing for rounding.
+        for jittered in &intervals {
+            assert!(*jittered > Duration::from_secs(1619), "{jittered:?}");
+            assert!(*jittered < Duration::from_secs(1981), "{jittered:?}");
+        }
+        assert!(intervals.iter().any(|jittered| *jittered != intervals[0]));
+    }
+
+    #[test]
+    fn test_with_jitter() {
+        let new_task = || {
+            let task_fn = TickTask {
+                n: Arc::new(AtomicI32::new(0)),
+            };
+            RepeatedTask::new(Duration::from_secs(1), Box::new(task_fn))
+        };
+        assert_eq!(0.0, new_task().jitter());
+        assert_eq!(0.1, new_task().with_jitter(0.1).jitter());
+        assert_eq!(0.0, new_task().with_jitter(f64::NAN).jitter());
+        assert_eq!(0.0, new_task().with_jitter(-1.0).jitter());
+        assert_eq!(MAX_JITTER, new_task().with_jitter(1.0).jitter());
+    }
 }
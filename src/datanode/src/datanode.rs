@@ -20,10 +20,12 @@ use std::time::Duration;
 use common_base::readable_size::ReadableSize;
 use common_base::Plugins;
 use common_error::ext::BoxedError;
+use common_greptimedb_telemetry::GreptimeDBTelemetryTask;
 pub use common_procedure::options::ProcedureConfig;
-use common_telemetry::info;
 use common_telemetry::logging::LoggingOptions;
+use common_telemetry::{info, warn};
 use meta_client::MetaClientOptions;
+use object_store::layers::DEFAULT_TIMEOUT_ESCALATION_FACTOR;
 use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
 use servers::heartbeat_options::HeartbeatOptions;
@@ -37,11 +39,17 @@ use storage::config::{
 use storage::scheduler::SchedulerConfig;
 
 use crate::error::{Result, ShutdownInstanceSnafu};
+use crate::greptimedb_telemetry::get_greptimedb_telemetry_task;
 use crate::heartbeat::HeartbeatTask;
 use crate::instance::{Instance, InstanceRef};
 use crate::server::Services;
 
-pub const DEFAULT_OBJECT_STORE_CACHE_SIZE: ReadableSize = ReadableSize(1024);
+/// Default max size of the local read cache of a remote object store.
+///
+/// The `cache_capacity` option is a byte size, e.g. `"1GB"`, rather than the
+/// number of cached files it used to be. Existing files in the cache are stat'ed
+/// on start to rebuild the cache index, so start up takes longer with a large cache.
+pub const DEFAULT_OBJECT_STORE_CACHE_SIZE: ReadableSize = ReadableSize::mb(256);
 
 /// Default data home in file storage
 const DEFAULT_DATA_HOME: &str = "/tmp/greptimedb";
@@ -57,8 +65,30 @@ pub enum ObjectStoreConfig {
     Gcs(GcsConfig),
 }
 
+/// Default max times to retry an object store operation.
+pub const DEFAULT_RETRY_MAX_TIMES: usize = 3;
+/// Default delay before the first retry of an object store operation.
+pub const DEFAULT_RETRY_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Default max delay between retries of an object store operation.
+pub const DEFAULT_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Default extra time allowed to write or read each MB of an object store.
+pub const DEFAULT_IO_TIMEOUT_PER_MB: Duration = Duration::from_secs(1);
+
+/// Version of the TLS protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TlsVersion {
+    #[serde(rename = "1.0")]
+    Tls1_0,
+    #[serde(rename = "1.1")]
+    Tls1_1,
+    #[serde(rename = "1.2")]
+    Tls1_2,
+    #[serde(rename = "1.3")]
+    Tls1_3,
+}
+
 /// Storage engine config
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct StorageConfig {
     /// Retention period for all tables.
@@ -68,6 +98,69 @@ pub struct StorageConfig {
     /// The precedence order is: ttl in table options > global ttl.
     #[serde(with = "humantime_serde")]
     pub global_ttl: Option<Duration>,
+    /// Ratio of successful object store operations to trace, in range `[0.0, 1.0]`.
+    ///
+    /// Failed operations are always traced.
+    pub trace_sample_rate: f64,
+    /// Timeout of the first attempt of a remote object store operation.
+    ///
+    /// Default value is `None`, which means no timeout.
+    #[serde(with = "humantime_serde")]
+    pub operation_timeout: Option<Duration>,
+    /// Factor to multiply the timeout by each time an attempt times out.
+    ///
+    /// The timeout is escalated at most twice across the retries of an operation,
+    /// and each operation starts from `operation_timeout`. Must be finite and not
+    /// larger than 100.0, values less than 1.0 mean no escalation.
+    pub timeout_escalation_factor: f64,
+    /// Base timeout to write a chunk of data to, or read the content of an object
+    /// from, a remote object store.
+    ///
+    /// The timeout grows by `io_timeout_per_mb` for each MB written or read.
+    /// Default value is `None`, which means no timeout.
+    #[serde(with = "humantime_serde")]
+    pub io_timeout: Option<Duration>,
+    /// Extra time allowed to write or read each MB of a remote object store.
+    #[serde(with = "humantime_serde")]
+    pub io_timeout_per_mb: Duration,
+    /// Max times to retry a remote object store operation.
+    ///
+    /// Only operations that fail with a temporary error (e.g. throttling or
+    /// a 5xx response) are retried.
+    pub retry_max_times: usize,
+    /// Delay before the first retry of a failed remote object store operation.
+    ///
+    /// The delay doubles on each retry, up to `retry_max_backoff`.
+    #[serde(with = "humantime_serde")]
+    pub retry_initial_backoff: Duration,
+    /// Max delay between retries of a failed remote object store operation.
+    #[serde(with = "humantime_serde")]
+    pub retry_max_backoff: Duration,
+    /// How long to cache object store list results.
+    ///
+    /// Default value is `None`, which means list results are not cached.
+    #[serde(with = "humantime_serde")]
+    pub list_cache_ttl: Option<Duration>,
+    /// Max number of concurrent object store operations, shared by all object
+    /// stores of the process.
+    ///
+    /// Only the value of the first object store is used. Default value is `None`,
+    /// which means no limit.
+    pub global_io_concurrency: Option<usize>,
+    /// Max number of concurrent operations of a remote object store, reached by
+    /// ramping up from a low concurrency as operations succeed.
+    ///
+    /// Default value is `None`, which means no ramp-up.
+    pub ramp_up_concurrency: Option<usize>,
+    /// Min TLS version of connections to remote object stores.
+    ///
+    /// Default value is `None`, which means the TLS library's default.
+    pub min_tls_version: Option<TlsVersion>,
+    /// Whether to check the object store is accessible on start.
+    ///
+    /// Startup fails if the object store is unreachable, the bucket doesn't
+    /// exist or the credential is rejected.
+    pub validate_on_start: bool,
     #[serde(flatten)]
     pub store: ObjectStoreConfig,
     pub compaction: CompactionConfig,
@@ -75,10 +168,42 @@ pub struct StorageConfig {
     pub flush: FlushConfig,
 }
 
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            global_ttl: None,
+            trace_sample_rate: 1.0,
+            operation_timeout: None,
+            timeout_escalation_factor: DEFAULT_TIMEOUT_ESCALATION_FACTOR,
+            io_timeout: None,
+            io_timeout_per_mb: DEFAULT_IO_TIMEOUT_PER_MB,
+            retry_max_times: DEFAULT_RETRY_MAX_TIMES,
+            retry_initial_backoff: DEFAULT_RETRY_INITIAL_BACKOFF,
+            retry_max_backoff: DEFAULT_RETRY_MAX_BACKOFF,
+            list_cache_ttl: None,
+            global_io_concurrency: None,
+            ramp_up_concurrency: None,
+            min_tls_version: None,
+            validate_on_start: false,
+            store: ObjectStoreConfig::default(),
+            compaction: CompactionConfig::default(),
+            manifest: RegionManifestConfig::default(),
+            flush: FlushConfig::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Default, Deserialize)]
 #[serde(default)]
 pub struct FileConfig {
     pub data_home: String,
+    /// Directory to write files to before moving them into `data_home`.
+    ///
+    /// Files are written to its `greptimedb_atomic_write` subdirectory, which is
+    /// cleaned on start. Defaults to `{data_home}.tmp/`, which is cleaned as a
+    /// whole. It must be on the same filesystem as `data_home`. Files are not
+    /// fsync'ed, the Fs service doesn't support it.
+    pub atomic_write_dir: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,7 +217,27 @@ pub struct S3Config {
     pub secret_access_key: SecretString,
     pub endpoint: Option<String>,
     pub region: Option<String>,
+    /// Addresses buckets as `bucket.endpoint/key` instead of `endpoint/bucket/key`.
+    ///
+    /// Path style is used by default as most S3 compatible services (e.g. MinIO)
+    /// support it.
+    pub enable_virtual_host_style: bool,
+    /// Detects the bucket region from the endpoint if `region` is not set.
+    ///
+    /// Only works for public buckets as the probe request is anonymous.
+    pub detect_region: bool,
+    /// Server side encryption algorithm, one of `AES256` and `aws:kms`.
+    ///
+    /// Default value is `None`, which means the bucket's default encryption.
+    pub server_side_encryption: Option<String>,
+    /// Id of the KMS key to encrypt objects with `aws:kms`.
+    ///
+    /// The key managed by the service is used if not set.
+    #[serde(skip_serializing)]
+    pub sse_kms_key_id: Option<SecretString>,
+    /// Local directory to cache reads from the object store, disabled if not set.
     pub cache_path: Option<String>,
+    /// Max total size of the files in `cache_path`, see [DEFAULT_OBJECT_STORE_CACHE_SIZE].
     pub cache_capacity: Option<ReadableSize>,
 }
 
@@ -105,8 +250,23 @@ pub struct OssConfig {
     pub access_key_id: SecretString,
     #[serde(skip_serializing)]
     pub access_key_secret: SecretString,
+    /// Endpoint of the OSS service, e.g. `https://oss-cn-hangzhou.aliyuncs.com`.
+    ///
+    /// Unlike S3, buckets are always addressed in virtual host style, i.e.
+    /// `bucket.endpoint/key`, so there is no `enable_virtual_host_style` option.
     pub endpoint: String,
+    /// Server side encryption algorithm, one of `AES256`, `KMS` and `SM4`.
+    ///
+    /// Default value is `None`, which means the bucket's default encryption.
+    pub server_side_encryption: Option<String>,
+    /// Id of the KMS key to encrypt objects with `KMS`.
+    ///
+    /// The key managed by the service is used if not set.
+    #[serde(skip_serializing)]
+    pub sse_kms_key_id: Option<SecretString>,
+    /// Local directory to cache reads from the object store, disabled if not set.
     pub cache_path: Option<String>,
+    /// Max total size of the files in `cache_path`, see [DEFAULT_OBJECT_STORE_CACHE_SIZE].
     pub cache_capacity: Option<ReadableSize>,
 }
 
@@ -121,7 +281,9 @@ pub struct AzblobConfig {
     pub account_key: SecretString,
     pub endpoint: String,
     pub sas_token: Option<String>,
+    /// Local directory to cache reads from the object store, disabled if not set.
     pub cache_path: Option<String>,
+    /// Max total size of the files in `cache_path`, see [DEFAULT_OBJECT_STORE_CACHE_SIZE].
     pub cache_capacity: Option<ReadableSize>,
 }
 
@@ -133,8 +295,15 @@ pub struct GcsConfig {
     pub scope: String,
     #[serde(skip_serializing)]
     pub credential_path: SecretString,
+    /// Base64 encoded content of the credential file.
+    ///
+    /// Used instead of `credential_path` when it is not empty.
+    #[serde(skip_serializing)]
+    pub credential: SecretString,
     pub endpoint: String,
+    /// Local directory to cache reads from the object store, disabled if not set.
     pub cache_path: Option<String>,
+    /// Max total size of the files in `cache_path`, see [DEFAULT_OBJECT_STORE_CACHE_SIZE].
     pub cache_capacity: Option<ReadableSize>,
 }
 
@@ -147,6 +316,10 @@ impl Default for S3Config {
             secret_access_key: SecretString::from(String::default()),
             endpoint: Option::default(),
             region: Option::default(),
+            enable_virtual_host_style: false,
+            detect_region: false,
+            server_side_encryption: Option::default(),
+            sse_kms_key_id: Option::default(),
             cache_path: Option::default(),
             cache_capacity: Option::default(),
         }
@@ -161,6 +334,8 @@ impl Default for OssConfig {
             access_key_id: SecretString::from(String::default()),
             access_key_secret: SecretString::from(String::default()),
             endpoint: String::default(),
+            server_side_encryption: Option::default(),
+            sse_kms_key_id: Option::default(),
             cache_path: Option::default(),
             cache_capacity: Option::default(),
         }
@@ -189,6 +364,7 @@ impl Default for GcsConfig {
             bucket: String::default(),
             scope: String::default(),
             credential_path: SecretString::from(String::default()),
+            credential: SecretString::from(String::default()),
             endpoint: String::default(),
             cache_path: Option::default(),
             cache_capacity: Option::default(),
@@ -200,6 +376,7 @@ impl Default for ObjectStoreConfig {
     fn default() -> Self {
         ObjectStoreConfig::File(FileConfig {
             data_home: DEFAULT_DATA_HOME.to_string(),
+            atomic_write_dir: None,
         })
     }
 }
@@ -400,6 +577,7 @@ pub struct Datanode {
     services: Option<Services>,
     instance: InstanceRef,
     heartbeat_task: Option<HeartbeatTask>,
+    greptimedb_telemetry_task: Arc<GreptimeDBTelemetryTask>,
 }
 
 impl Datanode {
@@ -409,11 +587,14 @@ impl Datanode {
             Mode::Distributed => Some(Services::try_new(instance.clone(), &opts).await?),
             Mode::Standalone => None,
         };
+        let greptimedb_telemetry_task =
+            get_greptimedb_telemetry_task(&opts, instance.catalog_manager().clone());
         Ok(Self {
             opts,
             services,
             instance,
             heartbeat_task,
+            greptimedb_telemetry_task,
         })
     }
 
@@ -429,6 +610,12 @@ impl Datanode {
         if let Some(task) = &self.heartbeat_task {
             task.start().await?;
         }
+        if let Err(e) = self
+            .greptimedb_telemetry_task
+            .start(common_runtime::bg_runtime())
+        {
+            warn!(e; "Failed to start telemetry task");
+        }
         Ok(())
     }
 
@@ -453,6 +640,9 @@ impl Datanode {
                 .map_err(BoxedError::new)
                 .context(ShutdownInstanceSnafu)?;
         }
+        if let Err(e) = self.greptimedb_telemetry_task.stop().await {
+            warn!(e; "Failed to stop telemetry task");
+        }
         let _ = self.instance.shutdown().await;
         Ok(())
     }
@@ -505,4 +695,117 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_gcs_config() {
+        let toml_str = r#"
+            [storage]
+            type = "Gcs"
+            bucket = "greptimedb"
+            root = "data"
+            scope = "https://www.googleapis.com/auth/devstorage.read_write"
+            credential = "Y3JlZGVudGlhbA=="
+        "#;
+        let opts: DatanodeOptions = toml::from_str(toml_str).unwrap();
+        match opts.storage.store {
+            ObjectStoreConfig::Gcs(cfg) => {
+                assert_eq!("greptimedb", cfg.bucket);
+                assert_eq!("data", cfg.root);
+                assert_eq!("Y3JlZGVudGlhbA==", cfg.credential.expose_secret());
+                assert!(cfg.credential_path.expose_secret().is_empty());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_azblob_config() {
+        let toml_str = r#"
+            [storage]
+            type = "Azblob"
+            container = "greptimedb"
+            root = "data"
+            account_name = "account_name"
+            account_key = "account_key"
+            endpoint = "https://account_name.blob.core.windows.net"
+        "#;
+        let opts: DatanodeOptions = toml::from_str(toml_str).unwrap();
+        match opts.storage.store {
+            ObjectStoreConfig::Azblob(cfg) => {
+                assert_eq!("greptimedb", cfg.container);
+                assert_eq!("data", cfg.root);
+                assert_eq!("account_name", cfg.account_name.expose_secret());
+                assert_eq!("account_key", cfg.account_key.expose_secret());
+                assert_eq!(None, cfg.sas_token);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_retry_options() {
+        let toml_str = r#"
+            [storage]
+            type = "S3"
+            retry_max_times = 5
+            retry_initial_backoff = "100ms"
+        "#;
+        let opts: DatanodeOptions = toml::from_str(toml_str).unwrap();
+        assert_eq!(5, opts.storage.retry_max_times);
+        assert_eq!(
+            Duration::from_millis(100),
+            opts.storage.retry_initial_backoff
+        );
+        assert_eq!(DEFAULT_RETRY_MAX_BACKOFF, opts.storage.retry_max_backoff);
+
+        let opts = DatanodeOptions::default();
+        assert_eq!(DEFAULT_RETRY_MAX_TIMES, opts.storage.retry_max_times);
+    }
+
+    #[test]
+    fn test_s3_addressing_style() {
+        let toml_str = r#"
+            [storage]
+            type = "S3"
+            bucket = "greptimedb"
+            endpoint = "http://127.0.0.1:9000"
+            region = "us-east-1"
+        "#;
+        let opts: DatanodeOptions = toml::from_str(toml_str).unwrap();
+        match opts.storage.store {
+            ObjectStoreConfig::S3(cfg) => {
+                assert_eq!(Some("us-east-1"), cfg.region.as_deref());
+                assert!(!cfg.enable_virtual_host_style);
+            }
+            _ => unreachable!(),
+        }
+
+        let toml_str = r#"
+            [storage]
+            type = "S3"
+            bucket = "greptimedb"
+            enable_virtual_host_style = true
+        "#;
+        let opts: DatanodeOptions = toml::from_str(toml_str).unwrap();
+        match opts.storage.store {
+            ObjectStoreConfig::S3(cfg) => assert!(cfg.enable_virtual_host_style),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_min_tls_version() {
+        let toml_str = r#"
+            [storage]
+            type = "S3"
+            access_key_id = "access_key_id"
+            secret_access_key = "secret_access_key"
+            min_tls_version = "1.2"
+        "#;
+        let opts: DatanodeOptions = toml::from_str(toml_str).unwrap();
+        assert_eq!(Some(TlsVersion::Tls1_2), opts.storage.min_tls_version);
+
+        let opts = DatanodeOptions::default();
+        assert_eq!(None, opts.storage.min_tls_version);
+    }
 }
@@ -264,6 +264,69 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display(
+        "Failed to probe region of bucket {} from {}, source: {}",
+        bucket,
+        endpoint,
+        source
+    ))]
+    ProbeBucketRegion {
+        endpoint: String,
+        bucket: String,
+        source: reqwest::Error,
+        location: Location,
+    },
+
+    #[snafu(display("Region of bucket {} not found in response from {}", bucket, endpoint))]
+    BucketRegionNotFound {
+        endpoint: String,
+        bucket: String,
+        location: Location,
+    },
+
+    #[snafu(display("Unsupported server side encryption for {}, {}", backend, reason))]
+    UnsupportedServerSideEncryption {
+        backend: String,
+        reason: String,
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Invalid timeout escalation factor {}, expect a finite value not larger than {}",
+        factor,
+        max
+    ))]
+    InvalidTimeoutEscalationFactor {
+        factor: f64,
+        max: f64,
+        location: Location,
+    },
+
+    #[snafu(display("Atomic write dir {} overlaps with the data home {}", dir, data_home))]
+    InvalidAtomicWriteDir {
+        dir: String,
+        data_home: String,
+        location: Location,
+    },
+
+    #[snafu(display("Access to object store is denied, source: {}", source))]
+    ObjectStoreAccessDenied {
+        source: object_store::Error,
+        location: Location,
+    },
+
+    #[snafu(display("Root of object store not found, source: {}", source))]
+    ObjectStoreRootNotFound {
+        source: object_store::Error,
+        location: Location,
+    },
+
+    #[snafu(display("Object store is unreachable, source: {}", source))]
+    ObjectStoreUnreachable {
+        source: object_store::Error,
+        location: Location,
+    },
+
     #[snafu(display("Runtime resource error, source: {}", source))]
     RuntimeResource {
         location: Location,
@@ -534,6 +597,9 @@ impl ErrorExt for Error {
             | MissingMetasrvOpts { .. }
             | ColumnNoneDefaultValue { .. }
             | MissingWalDirConfig { .. }
+            | UnsupportedServerSideEncryption { .. }
+            | InvalidTimeoutEscalationFactor { .. }
+            | InvalidAtomicWriteDir { .. }
             | PrepareImmutableTable { .. } => StatusCode::InvalidArguments,
 
             EncodeJson { .. } | DecodeJson { .. } | PayloadNotExist { .. } | Unexpected { .. } => {
@@ -565,7 +631,12 @@ impl ErrorExt for Error {
             | ShutdownServer { source, .. }
             | WaitForGrpcServing { source, .. } => source.status_code(),
 
-            InitBackend { .. } => StatusCode::StorageUnavailable,
+            InitBackend { .. }
+            | ProbeBucketRegion { .. }
+            | BucketRegionNotFound { .. }
+            | ObjectStoreRootNotFound { .. }
+            | ObjectStoreUnreachable { .. } => StatusCode::StorageUnavailable,
+            ObjectStoreAccessDenied { .. } => StatusCode::AccessDenied,
 
             OpenLogStore { source, .. } => source.status_code(),
             RuntimeResource { .. } => StatusCode::RuntimeResourcesExhausted,
@@ -0,0 +1,142 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use catalog::{datanode_stat, CatalogManagerRef};
+use common_greptimedb_telemetry::{
+    Collector, GreptimeDBTelemetry, GreptimeDBTelemetryTask, Mode as VersionReporterMode,
+    WorkloadStats, TELEMETRY_INTERVAL,
+};
+use servers::Mode;
+
+use crate::datanode::DatanodeOptions;
+
+/// Collects the telemetry data of a standalone node.
+///
+/// The compaction strategy is a per-table option without a node-wide setting, so
+/// it is reported as unknown. Region and table statistics come from one
+/// [datanode_stat] snapshot per report. Tables are counted from the region stats,
+/// so a table whose region stats fail to load is not counted.
+struct StandaloneGreptimeDBTelemetryCollector {
+    uuid: Option<String>,
+    retry: i32,
+    last_retry_time: Option<Instant>,
+    catalog_manager: CatalogManagerRef,
+    wal_sync_write: bool,
+}
+
+#[async_trait]
+impl Collector for StandaloneGreptimeDBTelemetryCollector {
+    fn get_mode(&self) -> VersionReporterMode {
+        VersionReporterMode::Standalone
+    }
+
+    async fn get_nodes(&self) -> Option<i32> {
+        Some(1)
+    }
+
+    fn get_wal_sync_policy(&self) -> String {
+        if self.wal_sync_write {
+            "sync_write"
+        } else {
+            "no_sync"
+        }
+        .to_string()
+    }
+
+    async fn get_max_region_bytes(&self) -> Option<i64> {
+        self.get_workload_stats().await.max_region_bytes
+    }
+
+    async fn get_regions(&self) -> Option<i64> {
+        self.get_workload_stats().await.regions
+    }
+
+    async fn get_tables(&self) -> Option<i64> {
+        self.get_workload_stats().await.tables
+    }
+
+    async fn get_workload_stats(&self) -> WorkloadStats {
+        let (region_number, region_stats) = datanode_stat(&self.catalog_manager).await;
+        let tables = region_stats
+            .iter()
+            .filter_map(|stat| stat.table_ident.as_ref())
+            .map(|table_ident| table_ident.table_id)
+            .collect::<HashSet<_>>();
+
+        WorkloadStats {
+            max_region_bytes: region_stats.iter().map(|stat| stat.approximate_bytes).max(),
+            regions: Some(region_number as i64),
+            tables: Some(tables.len() as i64),
+        }
+    }
+
+    fn get_retry(&self) -> i32 {
+        self.retry
+    }
+
+    fn inc_retry(&mut self) {
+        self.retry += 1;
+        self.last_retry_time = Some(Instant::now());
+    }
+
+    fn reset_retry(&mut self) {
+        self.retry = 0;
+        self.last_retry_time = None;
+    }
+
+    fn get_last_retry_time(&self) -> Option<Instant> {
+        self.last_retry_time
+    }
+
+    fn set_uuid_cache(&mut self, uuid: String) {
+        self.uuid = Some(uuid);
+    }
+
+    fn get_uuid_cache(&self) -> Option<String> {
+        self.uuid.clone()
+    }
+}
+
+/// Returns the telemetry task of the datanode.
+///
+/// Only the standalone mode reports from the datanode, the distributed mode
+/// reports from the metasrv. The task is disabled unless the crate is built
+/// with the `greptimedb-telemetry` feature.
+pub(crate) fn get_greptimedb_telemetry_task(
+    opts: &DatanodeOptions,
+    catalog_manager: CatalogManagerRef,
+) -> Arc<GreptimeDBTelemetryTask> {
+    if !cfg!(feature = "greptimedb-telemetry") {
+        return Arc::new(GreptimeDBTelemetryTask::disable());
+    }
+
+    match opts.mode {
+        Mode::Standalone => Arc::new(GreptimeDBTelemetryTask::enable(
+            TELEMETRY_INTERVAL,
+            GreptimeDBTelemetry::new(Box::new(StandaloneGreptimeDBTelemetryCollector {
+                uuid: None,
+                retry: 0,
+                last_retry_time: None,
+                catalog_manager,
+                wal_sync_write: opts.wal.sync_write,
+            })),
+        )),
+        Mode::Distributed => Arc::new(GreptimeDBTelemetryTask::disable()),
+    }
+}
@@ -161,7 +161,7 @@ impl Instance {
         compaction_scheduler: CompactionSchedulerRef<RaftEngineLogStore>,
         plugins: Arc<Plugins>,
     ) -> Result<(InstanceRef, Option<HeartbeatTask>)> {
-        let object_store = store::new_object_store(&opts.storage.store).await?;
+        let object_store = store::new_object_store(&opts.storage).await?;
         let log_store = Arc::new(create_log_store(&opts.storage.store, &opts.wal).await?);
 
         let mito_engine = Arc::new(DefaultEngine::new(
@@ -17,6 +17,7 @@
 
 pub mod datanode;
 pub mod error;
+mod greptimedb_telemetry;
 pub mod heartbeat;
 pub mod instance;
 pub mod metrics;
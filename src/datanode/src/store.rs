@@ -24,35 +24,121 @@ use std::path;
 use std::sync::Arc;
 
 use common_base::readable_size::ReadableSize;
-use common_telemetry::logging::info;
-use object_store::layers::{LoggingLayer, LruCacheLayer, MetricsLayer, RetryLayer, TracingLayer};
+use common_telemetry::logging::{info, warn};
+use futures::TryStreamExt;
+use object_store::layers::{
+    EscalatingTimeoutLayer, EscalatingTimeoutScopeLayer, ListCacheLayer, LoggingLayer,
+    LruCacheLayer, MetricsLayer, RampUpConcurrencyLayer, RetryLayer, SampledTracingLayer,
+    SharedConcurrentLimitLayer, SizeAwareTimeoutLayer, MAX_TIMEOUT_ESCALATION_FACTOR,
+};
 use object_store::services::Fs as FsBuilder;
-use object_store::{util, ObjectStore, ObjectStoreBuilder};
+use object_store::{util, ErrorKind, HttpClient, ObjectStore, ObjectStoreBuilder};
+use once_cell::sync::OnceCell;
 use snafu::prelude::*;
 
-use crate::datanode::{ObjectStoreConfig, DEFAULT_OBJECT_STORE_CACHE_SIZE};
+use crate::datanode::{
+    ObjectStoreConfig, StorageConfig, TlsVersion, DEFAULT_OBJECT_STORE_CACHE_SIZE,
+};
 use crate::error::{self, Result};
 
-pub(crate) async fn new_object_store(store_config: &ObjectStoreConfig) -> Result<ObjectStore> {
+/// Concurrency limit shared by all object stores of the process.
+///
+/// The limit is initialized by the first object store that enables it, a
+/// different limit of later stores is ignored with a warning.
+static GLOBAL_IO_LIMIT: OnceCell<SharedConcurrentLimitLayer> = OnceCell::new();
+
+pub(crate) async fn new_object_store(storage_config: &StorageConfig) -> Result<ObjectStore> {
+    let store_config = &storage_config.store;
+    let http_client = storage_config
+        .min_tls_version
+        .map(new_http_client)
+        .transpose()?;
     let object_store = match store_config {
         ObjectStoreConfig::File(file_config) => fs::new_fs_object_store(file_config).await,
-        ObjectStoreConfig::S3(s3_config) => s3::new_s3_object_store(s3_config).await,
-        ObjectStoreConfig::Oss(oss_config) => oss::new_oss_object_store(oss_config).await,
+        ObjectStoreConfig::S3(s3_config) => s3::new_s3_object_store(s3_config, http_client).await,
+        ObjectStoreConfig::Oss(oss_config) => {
+            oss::new_oss_object_store(oss_config, http_client).await
+        }
         ObjectStoreConfig::Azblob(azblob_config) => {
-            azblob::new_azblob_object_store(azblob_config).await
+            azblob::new_azblob_object_store(azblob_config, http_client).await
+        }
+        ObjectStoreConfig::Gcs(gcs_config) => {
+            gcs::new_gcs_object_store(gcs_config, http_client).await
         }
-        ObjectStoreConfig::Gcs(gcs_config) => gcs::new_gcs_object_store(gcs_config).await,
     }?;
 
+    // Validate the backend itself, so the result is neither retried, timed out
+    // nor served by the caches.
+    if storage_config.validate_on_start {
+        validate_object_store(&object_store).await?;
+    }
+
+    let object_store = if let Some(permits) = storage_config.global_io_concurrency {
+        let limit = GLOBAL_IO_LIMIT.get_or_init(|| SharedConcurrentLimitLayer::new(permits));
+        if limit.permits() != permits {
+            warn!(
+                "Ignore global_io_concurrency {} of the object store, the limit of the process is already {}",
+                permits,
+                limit.permits()
+            );
+        }
+        object_store.layer(limit.clone())
+    } else {
+        object_store
+    };
+
     // Enable retry layer and cache layer for non-fs object storages
     let object_store = if !matches!(store_config, ObjectStoreConfig::File(..)) {
+        // Place the ramp-up layer under the cache layer so cache hits don't count,
+        // and under the retry layer so it sees each throttled attempt.
+        let object_store = if let Some(max) = storage_config.ramp_up_concurrency {
+            object_store.layer(RampUpConcurrencyLayer::new(max))
+        } else {
+            object_store
+        };
         let object_store = create_object_store_with_cache(object_store, store_config).await?;
-        object_store.layer(RetryLayer::new().with_jitter())
+        // Place the timeout layer under the retry layer, which decides the number of attempts.
+        let object_store = if let Some(timeout) = storage_config.operation_timeout {
+            check_timeout_escalation_factor(storage_config.timeout_escalation_factor)?;
+            object_store.layer(
+                EscalatingTimeoutLayer::new(timeout)
+                    .with_escalation_factor(storage_config.timeout_escalation_factor),
+            )
+        } else {
+            object_store
+        };
+        let object_store = if let Some(timeout) = storage_config.io_timeout {
+            object_store.layer(SizeAwareTimeoutLayer::new(
+                timeout,
+                storage_config.io_timeout_per_mb,
+            ))
+        } else {
+            object_store
+        };
+        let object_store = object_store.layer(
+            RetryLayer::new()
+                .with_jitter()
+                .with_max_times(storage_config.retry_max_times)
+                .with_min_delay(storage_config.retry_initial_backoff)
+                .with_max_delay(storage_config.retry_max_backoff),
+        );
+        // Escalate the timeout across the retries of each operation.
+        if storage_config.operation_timeout.is_some() {
+            object_store.layer(EscalatingTimeoutScopeLayer)
+        } else {
+            object_store
+        }
     } else {
         object_store
     };
 
-    Ok(object_store
+    let object_store = if let Some(ttl) = storage_config.list_cache_ttl {
+        object_store.layer(ListCacheLayer::new(ttl))
+    } else {
+        object_store
+    };
+
+    let object_store = object_store
         .layer(MetricsLayer)
         .layer(
             LoggingLayer::default()
@@ -61,7 +147,57 @@ pub(crate) async fn new_object_store(store_config: &ObjectStoreConfig) -> Result
                 .with_error_level(Some("debug"))
                 .expect("input error level must be valid"),
         )
-        .layer(TracingLayer))
+        .layer(SampledTracingLayer::new(storage_config.trace_sample_rate));
+
+    Ok(object_store)
+}
+
+fn check_timeout_escalation_factor(factor: f64) -> Result<()> {
+    ensure!(
+        factor.is_finite() && factor <= MAX_TIMEOUT_ESCALATION_FACTOR,
+        error::InvalidTimeoutEscalationFactorSnafu {
+            factor,
+            max: MAX_TIMEOUT_ESCALATION_FACTOR,
+        }
+    );
+    Ok(())
+}
+
+/// Checks whether `object_store` is accessible by listing its root.
+pub(crate) async fn validate_object_store(object_store: &ObjectStore) -> Result<()> {
+    // Remote stores only send the request when we fetch the first page.
+    let result = match object_store.list("/").await {
+        Ok(mut lister) => lister.try_next().await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => {
+            info!("Object store is accessible");
+            Ok(())
+        }
+        Err(e) => match e.kind() {
+            ErrorKind::PermissionDenied => Err(e).context(error::ObjectStoreAccessDeniedSnafu),
+            ErrorKind::NotFound => Err(e).context(error::ObjectStoreRootNotFoundSnafu),
+            _ => Err(e).context(error::ObjectStoreUnreachableSnafu),
+        },
+    }
+}
+
+/// Returns an HTTP client for remote object stores that refuses connections
+/// negotiated below `min_tls_version`.
+fn new_http_client(min_tls_version: TlsVersion) -> Result<HttpClient> {
+    let builder =
+        reqwest::ClientBuilder::new().min_tls_version(to_reqwest_tls_version(min_tls_version));
+    HttpClient::build(builder).context(error::InitBackendSnafu)
+}
+
+fn to_reqwest_tls_version(version: TlsVersion) -> reqwest::tls::Version {
+    match version {
+        TlsVersion::Tls1_0 => reqwest::tls::Version::TLS_1_0,
+        TlsVersion::Tls1_1 => reqwest::tls::Version::TLS_1_1,
+        TlsVersion::Tls1_2 => reqwest::tls::Version::TLS_1_2,
+        TlsVersion::Tls1_3 => reqwest::tls::Version::TLS_1_3,
+    }
 }
 
 async fn create_object_store_with_cache(
@@ -128,3 +264,104 @@ pub(crate) fn clean_temp_dir(dir: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+
+    use common_test_util::temp_dir::create_temp_dir;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server, StatusCode};
+    use tokio::sync::oneshot;
+
+    use super::*;
+    use crate::datanode::{FileConfig, S3Config};
+
+    /// Starts a server that responds `status` to every request.
+    async fn start_mock_server(status: StatusCode) -> (SocketAddr, oneshot::Sender<()>) {
+        let make_svc = make_service_fn(move |_conn| async move {
+            Ok::<_, Infallible>(service_fn(move |_req| async move {
+                Response::builder().status(status).body(Body::empty())
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        let (tx, rx) = oneshot::channel();
+        let graceful = server.with_graceful_shutdown(async {
+            rx.await.ok();
+        });
+        let _ = tokio::spawn(graceful);
+        (addr, tx)
+    }
+
+    async fn new_mock_s3_store(addr: SocketAddr) -> ObjectStore {
+        let s3_config = S3Config {
+            bucket: "greptimedb".to_string(),
+            endpoint: Some(format!("http://{addr}")),
+            region: Some("us-east-1".to_string()),
+            access_key_id: "access_key_id".to_string().into(),
+            secret_access_key: "secret_access_key".to_string().into(),
+            ..Default::default()
+        };
+        s3::new_s3_object_store(&s3_config, None).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validate_object_store() {
+        let dir = create_temp_dir("test_validate_object_store");
+        let file_config = FileConfig {
+            data_home: dir.path().to_str().unwrap().to_string(),
+            atomic_write_dir: None,
+        };
+        let store = fs::new_fs_object_store(&file_config).await.unwrap();
+        validate_object_store(&store).await.unwrap();
+
+        let (addr, tx) = start_mock_server(StatusCode::FORBIDDEN).await;
+        let store = new_mock_s3_store(addr).await;
+        let err = validate_object_store(&store).await.unwrap_err();
+        assert!(
+            matches!(err, error::Error::ObjectStoreAccessDenied { .. }),
+            "unexpected err: {err}"
+        );
+        tx.send(()).unwrap();
+
+        let (addr, tx) = start_mock_server(StatusCode::NOT_FOUND).await;
+        let store = new_mock_s3_store(addr).await;
+        let err = validate_object_store(&store).await.unwrap_err();
+        assert!(
+            matches!(err, error::Error::ObjectStoreRootNotFound { .. }),
+            "unexpected err: {err}"
+        );
+        tx.send(()).unwrap();
+    }
+
+    #[test]
+    fn test_new_http_client() {
+        assert_eq!(
+            reqwest::tls::Version::TLS_1_2,
+            to_reqwest_tls_version(TlsVersion::Tls1_2)
+        );
+        assert_eq!(
+            reqwest::tls::Version::TLS_1_3,
+            to_reqwest_tls_version(TlsVersion::Tls1_3)
+        );
+
+        let _ = new_http_client(TlsVersion::Tls1_2).unwrap();
+        let _ = new_http_client(TlsVersion::Tls1_3).unwrap();
+    }
+
+    #[test]
+    fn test_check_timeout_escalation_factor() {
+        for factor in [0.5, 1.0, 2.0, MAX_TIMEOUT_ESCALATION_FACTOR] {
+            check_timeout_escalation_factor(factor).unwrap();
+        }
+        for factor in [f64::NAN, f64::INFINITY, MAX_TIMEOUT_ESCALATION_FACTOR + 1.0] {
+            let err = check_timeout_escalation_factor(factor).unwrap_err();
+            assert!(
+                matches!(err, error::Error::InvalidTimeoutEscalationFactor { .. }),
+                "unexpected err: {err}"
+            );
+        }
+    }
+}
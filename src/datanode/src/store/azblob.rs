@@ -14,14 +14,17 @@
 
 use common_telemetry::logging::info;
 use object_store::services::Azblob as AzureBuilder;
-use object_store::{util, ObjectStore};
+use object_store::{util, HttpClient, ObjectStore};
 use secrecy::ExposeSecret;
 use snafu::prelude::*;
 
 use crate::datanode::AzblobConfig;
 use crate::error::{self, Result};
 
-pub(crate) async fn new_azblob_object_store(azblob_config: &AzblobConfig) -> Result<ObjectStore> {
+pub(crate) async fn new_azblob_object_store(
+    azblob_config: &AzblobConfig,
+    http_client: Option<HttpClient>,
+) -> Result<ObjectStore> {
     let root = util::normalize_dir(&azblob_config.root);
 
     info!(
@@ -41,7 +44,30 @@ pub(crate) async fn new_azblob_object_store(azblob_config: &AzblobConfig) -> Res
         let _ = builder.sas_token(token);
     }
 
+    if let Some(client) = http_client {
+        let _ = builder.http_client(client);
+    }
+
     Ok(ObjectStore::new(builder)
         .context(error::InitBackendSnafu)?
         .finish())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_azblob_object_store() {
+        let azblob_config = AzblobConfig {
+            container: "greptimedb".to_string(),
+            root: "data".to_string(),
+            account_name: "account_name".to_string().into(),
+            // The account key must be base64 encoded.
+            account_key: "YWNjb3VudF9rZXk=".to_string().into(),
+            endpoint: "https://account_name.blob.core.windows.net".to_string(),
+            ..Default::default()
+        };
+        let _ = new_azblob_object_store(&azblob_config, None).await.unwrap();
+    }
+}
@@ -23,13 +23,29 @@ use crate::datanode::FileConfig;
 use crate::error::{self, Result};
 use crate::store;
 
+/// Subdirectory of a configured atomic write dir that the store owns.
+const ATOMIC_WRITE_SUBDIR: &str = "greptimedb_atomic_write/";
+
 pub(crate) async fn new_fs_object_store(file_config: &FileConfig) -> Result<ObjectStore> {
     let data_home = util::normalize_dir(&file_config.data_home);
     fs::create_dir_all(path::Path::new(&data_home))
         .context(error::CreateDirSnafu { dir: &data_home })?;
     info!("The file storage home is: {}", &data_home);
 
-    let atomic_write_dir = format!("{data_home}.tmp/");
+    // The dir is removed below, so only use a dedicated subdirectory of a
+    // configured dir, which may contain other files.
+    let atomic_write_dir = match &file_config.atomic_write_dir {
+        Some(dir) => format!("{}{ATOMIC_WRITE_SUBDIR}", util::normalize_dir(dir)),
+        None => format!("{data_home}.tmp/"),
+    };
+    // It must not contain the data either.
+    ensure!(
+        !atomic_write_dir.starts_with(&data_home) && !data_home.starts_with(&atomic_write_dir),
+        error::InvalidAtomicWriteDirSnafu {
+            dir: &atomic_write_dir,
+            data_home: &data_home,
+        }
+    );
     store::clean_temp_dir(&atomic_write_dir)?;
 
     let mut builder = FsBuilder::default();
@@ -41,3 +57,72 @@ pub(crate) async fn new_fs_object_store(file_config: &FileConfig) -> Result<Obje
 
     Ok(object_store)
 }
+
+#[cfg(test)]
+mod tests {
+    use common_test_util::temp_dir::create_temp_dir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_fs_object_store() {
+        let dir = create_temp_dir("test_new_fs_object_store");
+        // The data home doesn't exist yet.
+        let data_home = dir.path().join("data");
+        let file_config = FileConfig {
+            data_home: data_home.to_str().unwrap().to_string(),
+            atomic_write_dir: None,
+        };
+
+        let store = new_fs_object_store(&file_config).await.unwrap();
+        store.write("a/test_file", "Hello, World!").await.unwrap();
+        assert_eq!(
+            b"Hello, World!",
+            store.read("a/test_file").await.unwrap().as_slice()
+        );
+        assert!(data_home.join("a").join("test_file").exists());
+
+        // Opens the store again with existing data.
+        let store = new_fs_object_store(&file_config).await.unwrap();
+        assert_eq!(
+            b"Hello, World!",
+            store.read("a/test_file").await.unwrap().as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fs_object_store_atomic_write_dir() {
+        let dir = create_temp_dir("test_fs_object_store_atomic_write_dir");
+        let data_home = dir.path().join("data");
+        let atomic_write_dir = dir.path().join("atomic");
+        let file_config = FileConfig {
+            data_home: data_home.to_str().unwrap().to_string(),
+            atomic_write_dir: Some(atomic_write_dir.to_str().unwrap().to_string()),
+        };
+        // The configured dir may be shared with other files.
+        fs::create_dir_all(&atomic_write_dir).unwrap();
+        fs::write(atomic_write_dir.join("other_file"), "other").unwrap();
+
+        let store = new_fs_object_store(&file_config).await.unwrap();
+        store.write("test_file", "Hello, World!").await.unwrap();
+        assert!(data_home.join("test_file").exists());
+        assert!(atomic_write_dir.join(ATOMIC_WRITE_SUBDIR).exists());
+        assert!(!dir.path().join("data.tmp").exists());
+
+        // Only the subdirectory of the store is cleaned on start.
+        let _ = new_fs_object_store(&file_config).await.unwrap();
+        assert!(atomic_write_dir.join("other_file").exists());
+
+        // The atomic write dir can't be inside the data home.
+        let file_config = FileConfig {
+            data_home: data_home.to_str().unwrap().to_string(),
+            atomic_write_dir: Some(data_home.join("tmp").to_str().unwrap().to_string()),
+        };
+        let err = new_fs_object_store(&file_config).await.unwrap_err();
+        assert!(
+            matches!(err, error::Error::InvalidAtomicWriteDir { .. }),
+            "{err:?}"
+        );
+        assert!(data_home.join("test_file").exists());
+    }
+}
@@ -14,14 +14,17 @@
 
 use common_telemetry::logging::info;
 use object_store::services::Gcs as GCSBuilder;
-use object_store::{util, ObjectStore};
+use object_store::{util, HttpClient, ObjectStore};
 use secrecy::ExposeSecret;
 use snafu::prelude::*;
 
 use crate::datanode::GcsConfig;
 use crate::error::{self, Result};
 
-pub(crate) async fn new_gcs_object_store(gcs_config: &GcsConfig) -> Result<ObjectStore> {
+pub(crate) async fn new_gcs_object_store(
+    gcs_config: &GcsConfig,
+    http_client: Option<HttpClient>,
+) -> Result<ObjectStore> {
     let root = util::normalize_dir(&gcs_config.root);
     info!(
         "The gcs storage bucket is: {}, root is: {}",
@@ -33,10 +36,38 @@ pub(crate) async fn new_gcs_object_store(gcs_config: &GcsConfig) -> Result<Objec
         .root(&root)
         .bucket(&gcs_config.bucket)
         .scope(&gcs_config.scope)
-        .credential_path(gcs_config.credential_path.expose_secret())
         .endpoint(&gcs_config.endpoint);
 
+    // Prefer the inline credential so it can be passed without a file.
+    let credential = gcs_config.credential.expose_secret();
+    if credential.is_empty() {
+        let _ = builder.credential_path(gcs_config.credential_path.expose_secret());
+    } else {
+        let _ = builder.credential(credential);
+    }
+
+    if let Some(client) = http_client {
+        let _ = builder.http_client(client);
+    }
+
     Ok(ObjectStore::new(builder)
         .context(error::InitBackendSnafu)?
         .finish())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_gcs_object_store() {
+        let gcs_config = GcsConfig {
+            bucket: "greptimedb".to_string(),
+            root: "data".to_string(),
+            scope: "https://www.googleapis.com/auth/devstorage.read_write".to_string(),
+            credential: "Y3JlZGVudGlhbA==".to_string().into(),
+            ..Default::default()
+        };
+        let _ = new_gcs_object_store(&gcs_config, None).await.unwrap();
+    }
+}
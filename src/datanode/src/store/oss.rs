@@ -14,14 +14,23 @@
 
 use common_telemetry::logging::info;
 use object_store::services::Oss as OSSBuilder;
-use object_store::{util, ObjectStore};
+use object_store::{util, HttpClient, ObjectStore};
 use secrecy::ExposeSecret;
 use snafu::prelude::*;
 
 use crate::datanode::OssConfig;
 use crate::error::{self, Result};
 
-pub(crate) async fn new_oss_object_store(oss_config: &OssConfig) -> Result<ObjectStore> {
+/// Server side encryption algorithms supported by OSS.
+const SSE_ALGORITHMS: [&str; 3] = ["AES256", "KMS", "SM4"];
+const SSE_KMS: &str = "KMS";
+
+pub(crate) async fn new_oss_object_store(
+    oss_config: &OssConfig,
+    http_client: Option<HttpClient>,
+) -> Result<ObjectStore> {
+    check_server_side_encryption(oss_config)?;
+
     let root = util::normalize_dir(&oss_config.root);
     info!(
         "The oss storage bucket is: {}, root is: {}",
@@ -36,7 +45,93 @@ pub(crate) async fn new_oss_object_store(oss_config: &OssConfig) -> Result<Objec
         .access_key_id(oss_config.access_key_id.expose_secret())
         .access_key_secret(oss_config.access_key_secret.expose_secret());
 
+    if let Some(algorithm) = &oss_config.server_side_encryption {
+        let _ = builder.server_side_encryption(algorithm);
+    }
+    if let Some(key_id) = &oss_config.sse_kms_key_id {
+        let _ = builder.server_side_encryption_key_id(key_id.expose_secret());
+    }
+
+    if let Some(client) = http_client {
+        let _ = builder.http_client(client);
+    }
+
     Ok(ObjectStore::new(builder)
         .context(error::InitBackendSnafu)?
         .finish())
 }
+
+fn check_server_side_encryption(oss_config: &OssConfig) -> Result<()> {
+    let algorithm = oss_config.server_side_encryption.as_deref();
+    if let Some(algorithm) = algorithm {
+        ensure!(
+            SSE_ALGORITHMS.contains(&algorithm),
+            error::UnsupportedServerSideEncryptionSnafu {
+                backend: "oss",
+                reason: format!("unknown algorithm {algorithm}"),
+            }
+        );
+    }
+    ensure!(
+        oss_config.sse_kms_key_id.is_none() || algorithm == Some(SSE_KMS),
+        error::UnsupportedServerSideEncryptionSnafu {
+            backend: "oss",
+            reason: format!("sse_kms_key_id requires algorithm {SSE_KMS}"),
+        }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oss_config() -> OssConfig {
+        OssConfig {
+            bucket: "greptimedb".to_string(),
+            root: "data".to_string(),
+            endpoint: "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            access_key_id: "access_key_id".to_string().into(),
+            access_key_secret: "access_key_secret".to_string().into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oss_server_side_encryption() {
+        for algorithm in SSE_ALGORITHMS {
+            let config = OssConfig {
+                server_side_encryption: Some(algorithm.to_string()),
+                ..oss_config()
+            };
+            let _ = new_oss_object_store(&config, None).await.unwrap();
+        }
+
+        let config = OssConfig {
+            server_side_encryption: Some("KMS".to_string()),
+            sse_kms_key_id: Some("key_id".to_string().into()),
+            ..oss_config()
+        };
+        let _ = new_oss_object_store(&config, None).await.unwrap();
+
+        let config = OssConfig {
+            server_side_encryption: Some("aws:kms".to_string()),
+            ..oss_config()
+        };
+        let err = new_oss_object_store(&config, None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            error::Error::UnsupportedServerSideEncryption { .. }
+        ));
+
+        let config = OssConfig {
+            sse_kms_key_id: Some("key_id".to_string().into()),
+            ..oss_config()
+        };
+        let err = new_oss_object_store(&config, None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            error::Error::UnsupportedServerSideEncryption { .. }
+        ));
+    }
+}
@@ -12,16 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
 use common_telemetry::logging::info;
 use object_store::services::S3 as S3Builder;
-use object_store::{util, ObjectStore};
+use object_store::{util, HttpClient, ObjectStore};
+use once_cell::sync::Lazy;
 use secrecy::ExposeSecret;
 use snafu::prelude::*;
 
 use crate::datanode::S3Config;
 use crate::error::{self, Result};
 
-pub(crate) async fn new_s3_object_store(s3_config: &S3Config) -> Result<ObjectStore> {
+/// Endpoint to probe if no endpoint is configured.
+const DEFAULT_S3_ENDPOINT: &str = "https://s3.amazonaws.com";
+/// Response header that carries the region of a bucket.
+const BUCKET_REGION_HEADER: &str = "x-amz-bucket-region";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bucket regions detected by [probe_bucket_region()], keyed by (endpoint, bucket).
+static BUCKET_REGIONS: Lazy<Mutex<HashMap<(String, String), String>>> = Lazy::new(Default::default);
+
+pub(crate) async fn new_s3_object_store(
+    s3_config: &S3Config,
+    http_client: Option<HttpClient>,
+) -> Result<ObjectStore> {
+    check_server_side_encryption(s3_config)?;
+
     let root = util::normalize_dir(&s3_config.root);
 
     info!(
@@ -39,11 +58,324 @@ pub(crate) async fn new_s3_object_store(s3_config: &S3Config) -> Result<ObjectSt
     if s3_config.endpoint.is_some() {
         let _ = builder.endpoint(s3_config.endpoint.as_ref().unwrap());
     }
+    if s3_config.enable_virtual_host_style {
+        let _ = builder.enable_virtual_host_style();
+    }
     if s3_config.region.is_some() {
         let _ = builder.region(s3_config.region.as_ref().unwrap());
+    } else if s3_config.detect_region {
+        let endpoint = s3_config.endpoint.as_deref().unwrap_or(DEFAULT_S3_ENDPOINT);
+        let region = probe_bucket_region(endpoint, &s3_config.bucket).await?;
+        info!(
+            "Detected region {} for s3 bucket {}",
+            region, s3_config.bucket
+        );
+        let _ = builder.region(&region);
+    }
+
+    if let Some(algorithm) = &s3_config.server_side_encryption {
+        let _ = builder.server_side_encryption(algorithm);
+    }
+    if let Some(key_id) = &s3_config.sse_kms_key_id {
+        let _ = builder.server_side_encryption_aws_kms_key_id(key_id.expose_secret());
+    }
+
+    if let Some(client) = http_client {
+        let _ = builder.http_client(client);
     }
 
     Ok(ObjectStore::new(builder)
         .context(error::InitBackendSnafu)?
         .finish())
 }
+
+/// Server side encryption algorithms supported by S3.
+const SSE_ALGORITHMS: [&str; 2] = ["AES256", "aws:kms"];
+const SSE_KMS: &str = "aws:kms";
+
+fn check_server_side_encryption(s3_config: &S3Config) -> Result<()> {
+    let algorithm = s3_config.server_side_encryption.as_deref();
+    if let Some(algorithm) = algorithm {
+        ensure!(
+            SSE_ALGORITHMS.contains(&algorithm),
+            error::UnsupportedServerSideEncryptionSnafu {
+                backend: "s3",
+                reason: format!("unknown algorithm {algorithm}"),
+            }
+        );
+    }
+    ensure!(
+        s3_config.sse_kms_key_id.is_none() || algorithm == Some(SSE_KMS),
+        error::UnsupportedServerSideEncryptionSnafu {
+            backend: "s3",
+            reason: format!("sse_kms_key_id requires algorithm {SSE_KMS}"),
+        }
+    );
+    Ok(())
+}
+
+/// Detects the region of `bucket` by sending an anonymous `HEAD` request to `endpoint`.
+///
+/// S3 compatible services return the bucket region in the `x-amz-bucket-region` header
+/// even if they redirect or deny the request, so we don't follow redirects. Results are
+/// cached for the lifetime of the process.
+pub(crate) async fn probe_bucket_region(endpoint: &str, bucket: &str) -> Result<String> {
+    let key = (endpoint.to_string(), bucket.to_string());
+    let cached = BUCKET_REGIONS.lock().unwrap().get(&key).cloned();
+    if let Some(region) = cached {
+        return Ok(region);
+    }
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .context(error::ProbeBucketRegionSnafu { endpoint, bucket })?;
+    let url = format!("{}/{}", endpoint.trim_end_matches('/'), bucket);
+    let response = client
+        .head(&url)
+        .send()
+        .await
+        .context(error::ProbeBucketRegionSnafu { endpoint, bucket })?;
+
+    let region = response
+        .headers()
+        .get(BUCKET_REGION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .context(error::BucketRegionNotFoundSnafu { endpoint, bucket })?
+        .to_string();
+    let _ = BUCKET_REGIONS.lock().unwrap().insert(key, region.clone());
+
+    Ok(region)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, HeaderMap, Method, Response, Server, StatusCode};
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    /// Starts a server that redirects every request and returns the region hint
+    /// for `bucket`.
+    async fn start_mock_server(
+        bucket: &'static str,
+        counter: Arc<AtomicUsize>,
+    ) -> (SocketAddr, oneshot::Sender<()>) {
+        let make_svc = make_service_fn(move |_conn| {
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let _ = counter.fetch_add(1, Ordering::Relaxed);
+                    let mut builder = Response::builder().status(StatusCode::MOVED_PERMANENTLY);
+                    if req.method() == Method::HEAD && req.uri().path() == format!("/{bucket}") {
+                        builder = builder.header(BUCKET_REGION_HEADER, "eu-west-2");
+                    }
+                    async move { builder.body(Body::empty()) }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        let (tx, rx) = oneshot::channel();
+        let graceful = server.with_graceful_shutdown(async {
+            rx.await.ok();
+        });
+        let _ = tokio::spawn(graceful);
+        (addr, tx)
+    }
+
+    type RecordedRequests = Arc<std::sync::Mutex<Vec<(String, HeaderMap)>>>;
+
+    /// Starts a server that responds 404 to every request and records request paths
+    /// and headers.
+    async fn start_recording_server(
+        requests: RecordedRequests,
+    ) -> (SocketAddr, oneshot::Sender<()>) {
+        let make_svc = make_service_fn(move |_conn| {
+            let requests = requests.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    requests
+                        .lock()
+                        .unwrap()
+                        .push((req.uri().path().to_string(), req.headers().clone()));
+                    async move {
+                        Response::builder()
+                            .status(StatusCode::NOT_FOUND)
+                            .body(Body::empty())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        let (tx, rx) = oneshot::channel();
+        let graceful = server.with_graceful_shutdown(async {
+            rx.await.ok();
+        });
+        let _ = tokio::spawn(graceful);
+        (addr, tx)
+    }
+
+    #[tokio::test]
+    async fn test_s3_path_style() {
+        let requests = RecordedRequests::default();
+        let (addr, tx) = start_recording_server(requests.clone()).await;
+
+        let s3_config = S3Config {
+            bucket: "greptimedb".to_string(),
+            root: "data".to_string(),
+            endpoint: Some(format!("http://{addr}")),
+            region: Some("us-east-1".to_string()),
+            access_key_id: "access_key_id".to_string().into(),
+            secret_access_key: "secret_access_key".to_string().into(),
+            ..Default::default()
+        };
+        let store = new_s3_object_store(&s3_config, None).await.unwrap();
+        let err = store.stat("test_file").await.unwrap_err();
+        assert_eq!(object_store::ErrorKind::NotFound, err.kind());
+
+        // The bucket is in the path.
+        let paths: Vec<_> = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect();
+        assert_eq!(vec!["/greptimedb/data/test_file".to_string()], paths);
+
+        tx.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_s3_virtual_host_style() {
+        let requests = RecordedRequests::default();
+        let (addr, tx) = start_recording_server(requests.clone()).await;
+
+        // Resolves the bucket host to the local server, as a wildcard DNS would do.
+        let builder = reqwest::ClientBuilder::new().resolve("greptimedb.s3.test", addr);
+        let http_client = HttpClient::build(builder).unwrap();
+        let s3_config = S3Config {
+            bucket: "greptimedb".to_string(),
+            root: "data".to_string(),
+            endpoint: Some(format!("http://s3.test:{}", addr.port())),
+            region: Some("us-east-1".to_string()),
+            enable_virtual_host_style: true,
+            access_key_id: "access_key_id".to_string().into(),
+            secret_access_key: "secret_access_key".to_string().into(),
+            ..Default::default()
+        };
+        let store = new_s3_object_store(&s3_config, Some(http_client))
+            .await
+            .unwrap();
+        let err = store.stat("test_file").await.unwrap_err();
+        assert_eq!(object_store::ErrorKind::NotFound, err.kind());
+
+        // The bucket is in the host instead of the path.
+        let requests = requests.lock().unwrap();
+        assert_eq!(1, requests.len());
+        let (path, headers) = &requests[0];
+        assert_eq!("/data/test_file", path);
+        assert_eq!(
+            format!("greptimedb.s3.test:{}", addr.port()),
+            headers.get("host").unwrap().to_str().unwrap()
+        );
+
+        tx.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_s3_server_side_encryption() {
+        let requests = RecordedRequests::default();
+        let (addr, tx) = start_recording_server(requests.clone()).await;
+
+        let s3_config = S3Config {
+            bucket: "greptimedb".to_string(),
+            root: "data".to_string(),
+            endpoint: Some(format!("http://{addr}")),
+            region: Some("us-east-1".to_string()),
+            access_key_id: "access_key_id".to_string().into(),
+            secret_access_key: "secret_access_key".to_string().into(),
+            server_side_encryption: Some("aws:kms".to_string()),
+            sse_kms_key_id: Some("key_id".to_string().into()),
+            ..Default::default()
+        };
+        let store = new_s3_object_store(&s3_config, None).await.unwrap();
+        // The mock server always responds 404.
+        assert!(store.write("test_file", "hello").await.is_err());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(1, requests.len());
+        let headers = &requests[0].1;
+        assert_eq!("aws:kms", headers["x-amz-server-side-encryption"]);
+        assert_eq!(
+            "key_id",
+            headers["x-amz-server-side-encryption-aws-kms-key-id"]
+        );
+
+        tx.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_s3_invalid_server_side_encryption() {
+        let s3_config = S3Config {
+            bucket: "greptimedb".to_string(),
+            server_side_encryption: Some("SM4".to_string()),
+            ..Default::default()
+        };
+        let err = new_s3_object_store(&s3_config, None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            error::Error::UnsupportedServerSideEncryption { .. }
+        ));
+
+        // Key id without kms.
+        let s3_config = S3Config {
+            bucket: "greptimedb".to_string(),
+            server_side_encryption: Some("AES256".to_string()),
+            sse_kms_key_id: Some("key_id".to_string().into()),
+            ..Default::default()
+        };
+        let err = new_s3_object_store(&s3_config, None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            error::Error::UnsupportedServerSideEncryption { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_probe_bucket_region() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (addr, tx) = start_mock_server("public-bucket", counter.clone()).await;
+        let endpoint = format!("http://{addr}");
+
+        let region = probe_bucket_region(&endpoint, "public-bucket")
+            .await
+            .unwrap();
+        assert_eq!("eu-west-2", region);
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+
+        // The region is cached.
+        let region = probe_bucket_region(&endpoint, "public-bucket")
+            .await
+            .unwrap();
+        assert_eq!("eu-west-2", region);
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+
+        // No region hint in response.
+        let err = probe_bucket_region(&endpoint, "other-bucket")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, error::Error::BucketRegionNotFound { .. }));
+        assert_eq!(2, counter.load(Ordering::Relaxed));
+
+        tx.send(()).unwrap();
+    }
+}
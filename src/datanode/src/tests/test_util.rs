@@ -76,6 +76,7 @@ fn create_tmp_dir_and_datanode_opts(name: &str) -> (DatanodeOptions, TestGuard)
         storage: StorageConfig {
             store: ObjectStoreConfig::File(FileConfig {
                 data_home: data_tmp_dir.path().to_str().unwrap().to_string(),
+                atomic_write_dir: None,
             }),
             ..Default::default()
         },
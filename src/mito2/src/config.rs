@@ -30,6 +30,8 @@ pub struct MitoConfig {
     /// Number of region workers (default 1).
     pub num_workers: usize,
     /// Request channel size of each worker (default 128).
+    ///
+    /// Requests to a worker whose channel is full are rejected.
     pub worker_channel_size: usize,
     /// Max batch size for a worker to handle requests (default 64).
     pub worker_request_batch_size: usize,
@@ -40,6 +42,8 @@ pub struct MitoConfig {
     pub manifest_checkpoint_interval: u64,
     /// Manifest compression type (default uncompressed).
     pub manifest_compress_type: CompressionType,
+    /// Whether to read back and verify manifest files after writing them (default false).
+    pub verify_manifest_writes: bool,
 }
 
 impl Default for MitoConfig {
@@ -50,6 +54,7 @@ impl Default for MitoConfig {
             worker_request_batch_size: 64,
             manifest_checkpoint_interval: 10,
             manifest_compress_type: CompressionType::Uncompressed,
+            verify_manifest_writes: false,
         }
     }
 }
@@ -19,15 +19,21 @@ mod tests;
 
 use std::sync::Arc;
 
+use common_telemetry::logging;
+use dashmap::DashMap;
+use datatypes::schema::SchemaRef;
 use object_store::ObjectStore;
-use snafu::ResultExt;
+use snafu::{ensure, OptionExt, ResultExt};
 use store_api::logstore::LogStore;
 use store_api::storage::RegionId;
+use tokio::sync::{Mutex, OwnedMutexGuard};
 
 use crate::config::MitoConfig;
-use crate::error::{RecvSnafu, Result};
-pub use crate::worker::request::CreateRequest;
-use crate::worker::request::{RegionRequest, RequestBody};
+use crate::error::{InvalidRequestSnafu, RecvSnafu, RegionNotFoundSnafu, Result};
+pub use crate::worker::request::{AlterKind, AlterRequest, CreateRequest, OpenRequest};
+use crate::worker::request::{
+    CloseRequest, DropRequest, RegionOptions, RegionRequest, RenameRequest, RequestBody,
+};
 use crate::worker::WorkerGroup;
 
 /// Region engine implementation for timeseries data.
@@ -60,16 +66,85 @@ impl MitoEngine {
         self.inner.create_region(request).await
     }
 
+    /// Creates multiple regions in one call.
+    ///
+    /// Requests are sent to region workers together and the returned vector
+    /// holds the result of each request in the same order, so failing to
+    /// create one region doesn't affect others. Returns an error if the
+    /// engine fails to submit requests to workers.
+    pub async fn create_regions(&self, requests: Vec<CreateRequest>) -> Result<Vec<Result<()>>> {
+        self.inner.create_regions(requests).await
+    }
+
+    /// Opens an existing region from its manifest.
+    ///
+    /// Opening a region that is already opened does nothing.
+    pub async fn open_region(&self, request: OpenRequest) -> Result<()> {
+        self.inner.open_region(request).await
+    }
+
+    /// Closes the specific region and releases its resources.
+    ///
+    /// Data of the region is kept so the region can be opened again.
+    pub async fn close_region(&self, region_id: RegionId) -> Result<()> {
+        self.inner.close_region(region_id).await
+    }
+
+    /// Drops the specific region and removes its manifest and SST files.
+    pub async fn drop_region(&self, region_id: RegionId) -> Result<()> {
+        self.inner.drop_region(region_id).await
+    }
+
+    /// Alters the schema of an existing region.
+    pub async fn alter_region(&self, request: AlterRequest) -> Result<()> {
+        self.inner.alter_region(request).await
+    }
+
+    /// Renames the region `region_id` to `new_region_id`, keeping its data
+    /// directory and files.
+    ///
+    /// The region is unavailable under both ids while renaming. It is reopened
+    /// under `region_id` if renaming fails, e.g. `new_region_id` exists. Other
+    /// open, close and drop requests to `region_id` wait until renaming finishes.
+    ///
+    /// Returns [InvalidRequest](crate::error::Error::InvalidRequest) if
+    /// `new_region_id` is the same as `region_id`.
+    pub async fn rename_region(&self, region_id: RegionId, new_region_id: RegionId) -> Result<()> {
+        self.inner.rename_region(region_id, new_region_id).await
+    }
+
     /// Returns true if the specific region exists.
     pub fn is_region_exists(&self, region_id: RegionId) -> bool {
         self.inner.workers.is_region_exists(region_id)
     }
+
+    /// Returns ids of all opened regions, sorted by id.
+    ///
+    /// Regions created or closed concurrently may or may not be included.
+    pub fn region_ids(&self) -> Vec<RegionId> {
+        self.inner.workers.region_ids()
+    }
+
+    /// Returns the latest schema of the specific region.
+    ///
+    /// Columns in the schema are in the same order as the region's columns.
+    pub fn region_schema(&self, region_id: RegionId) -> Result<SchemaRef> {
+        let region = self
+            .inner
+            .workers
+            .get_region(region_id)
+            .context(RegionNotFoundSnafu { region_id })?;
+
+        Ok(region.metadata().schema.clone())
+    }
 }
 
 /// Inner struct of [MitoEngine].
 struct EngineInner {
     /// Region workers group.
     workers: WorkerGroup,
+    /// Locks to serialize requests that change whether a region is opened.
+    region_locks: DashMap<RegionId, Arc<Mutex<()>>>,
 }
 
 impl EngineInner {
@@ -81,6 +156,7 @@ impl EngineInner {
     ) -> EngineInner {
         EngineInner {
             workers: WorkerGroup::start(config, log_store, object_store),
+            region_locks: DashMap::new(),
         }
     }
 
@@ -96,4 +172,130 @@ impl EngineInner {
 
         receiver.await.context(RecvSnafu)?
     }
+
+    /// Creates multiple regions.
+    async fn create_regions(&self, create_requests: Vec<CreateRequest>) -> Result<Vec<Result<()>>> {
+        // Submits all requests first so workers can handle them concurrently.
+        let mut receivers = Vec::with_capacity(create_requests.len());
+        for create_request in create_requests {
+            let (request, receiver) = RegionRequest::from_body(RequestBody::Create(create_request));
+            // Waits for channel space instead of failing requests beyond the
+            // channel size.
+            self.workers.send_to_worker(request).await?;
+            receivers.push(receiver);
+        }
+
+        let mut results = Vec::with_capacity(receivers.len());
+        for receiver in receivers {
+            results.push(receiver.await.context(RecvSnafu).and_then(|res| res));
+        }
+
+        Ok(results)
+    }
+
+    /// Opens an existing region.
+    async fn open_region(&self, open_request: OpenRequest) -> Result<()> {
+        let _guard = self.lock_region(open_request.region_id).await;
+        let (request, receiver) = RegionRequest::from_body(RequestBody::Open(open_request));
+        self.workers.submit_to_worker(request).await?;
+
+        receiver.await.context(RecvSnafu)?
+    }
+
+    /// Closes a region.
+    async fn close_region(&self, region_id: RegionId) -> Result<()> {
+        let _guard = self.lock_region(region_id).await;
+        let (request, receiver) =
+            RegionRequest::from_body(RequestBody::Close(CloseRequest { region_id }));
+        self.workers.submit_to_worker(request).await?;
+
+        receiver.await.context(RecvSnafu)?
+    }
+
+    /// Drops a region.
+    async fn drop_region(&self, region_id: RegionId) -> Result<()> {
+        let _guard = self.lock_region(region_id).await;
+        let (request, receiver) =
+            RegionRequest::from_body(RequestBody::Drop(DropRequest { region_id }));
+        self.workers.submit_to_worker(request).await?;
+
+        receiver.await.context(RecvSnafu)?
+    }
+
+    /// Alters a region.
+    async fn alter_region(&self, alter_request: AlterRequest) -> Result<()> {
+        let (request, receiver) = RegionRequest::from_body(RequestBody::Alter(alter_request));
+        self.workers.submit_to_worker(request).await?;
+
+        receiver.await.context(RecvSnafu)?
+    }
+
+    /// Renames a region.
+    async fn rename_region(&self, region_id: RegionId, new_region_id: RegionId) -> Result<()> {
+        ensure!(
+            region_id != new_region_id,
+            InvalidRequestSnafu {
+                region_id,
+                reason: "cannot rename a region to itself",
+            }
+        );
+        // Holds the lock until the region is opened under either id, otherwise
+        // a concurrent open request may reopen the closed region under the old
+        // id while we are renaming it.
+        let _guard = self.lock_region(region_id).await;
+        let region_dir = self
+            .workers
+            .get_region(region_id)
+            .context(RegionNotFoundSnafu { region_id })?
+            .region_dir
+            .clone();
+
+        // The new id may belong to another worker, so we close the region first
+        // and then the worker of the new id opens it. The worker checks whether
+        // the new id exists, which is consistent with concurrent creates. The
+        // manifest keeps the old id until the region is opened under the new
+        // one, so we can reopen it under the old id on failure.
+        self.send_and_wait(RequestBody::Close(CloseRequest { region_id }))
+            .await?;
+        let result = self
+            .send_and_wait(RequestBody::Rename(RenameRequest {
+                region_id: new_region_id,
+                old_region_id: region_id,
+                region_dir: region_dir.clone(),
+            }))
+            .await;
+        if let Err(e) = result {
+            let reopen = self
+                .send_and_wait(RequestBody::Open(OpenRequest {
+                    region_id,
+                    region_dir,
+                    options: RegionOptions::default(),
+                }))
+                .await;
+            if let Err(reopen_err) = reopen {
+                logging::error!(
+                    reopen_err; "Failed to reopen region {} after failing to rename it to {}",
+                    region_id, new_region_id
+                );
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Locks the specific region until the returned guard is dropped.
+    async fn lock_region(&self, region_id: RegionId) -> OwnedMutexGuard<()> {
+        let lock = self.region_locks.entry(region_id).or_default().clone();
+        lock.lock_owned().await
+    }
+
+    /// Sends a request to its worker, waiting for channel space, and waits for
+    /// the result.
+    async fn send_and_wait(&self, body: RequestBody) -> Result<()> {
+        let (request, receiver) = RegionRequest::from_body(body);
+        self.workers.send_to_worker(request).await?;
+
+        receiver.await.context(RecvSnafu)?
+    }
 }
@@ -14,11 +14,18 @@
 
 //! Tests for mito engine.
 
+use common_error::ext::ErrorExt;
+use datatypes::prelude::ConcreteDataType;
+use datatypes::schema::{ColumnDefaultConstraint, ColumnSchema};
+use datatypes::value::Value;
+use futures::future::join_all;
 use store_api::storage::RegionId;
 
 use super::*;
 use crate::error::Error;
+use crate::metadata::{ColumnMetadata, SemanticType};
 use crate::test_util::{CreateRequestBuilder, TestEnv};
+use crate::worker::request::RegionOptions;
 
 #[tokio::test]
 async fn test_engine_new_stop() {
@@ -74,3 +81,542 @@ async fn test_engine_create_existing_region() {
         "unexpected err: {err}"
     );
 }
+
+#[tokio::test]
+async fn test_engine_create_regions() {
+    let env = TestEnv::new("create-regions");
+    let engine = env
+        .create_engine(MitoConfig {
+            num_workers: 4,
+            ..Default::default()
+        })
+        .await;
+
+    let requests = (0..100)
+        .map(|i| {
+            CreateRequestBuilder::new(RegionId::new(1, i))
+                .region_dir(&format!("region-{i}"))
+                .build()
+        })
+        .collect();
+    let results = engine.create_regions(requests).await.unwrap();
+    assert_eq!(100, results.len());
+    assert!(results.iter().all(|res| res.is_ok()));
+    for i in 0..100 {
+        assert!(engine.is_region_exists(RegionId::new(1, i)));
+    }
+}
+
+#[tokio::test]
+async fn test_engine_region_ids() {
+    let env = TestEnv::new("region-ids");
+    let engine = env
+        .create_engine(MitoConfig {
+            num_workers: 4,
+            ..Default::default()
+        })
+        .await;
+    assert!(engine.region_ids().is_empty());
+
+    let mut region_ids = Vec::new();
+    for table_id in [3, 1, 2] {
+        for region_number in [2, 0, 1] {
+            let region_id = RegionId::new(table_id, region_number);
+            let request = CreateRequestBuilder::new(region_id)
+                .region_dir(&format!("region-{table_id}-{region_number}"))
+                .build();
+            engine.create_region(request).await.unwrap();
+            region_ids.push(region_id);
+        }
+    }
+    region_ids.sort_unstable_by_key(|region_id| region_id.as_u64());
+    assert_eq!(region_ids, engine.region_ids());
+
+    // Closed regions are excluded.
+    engine.close_region(RegionId::new(2, 1)).await.unwrap();
+    region_ids.retain(|region_id| *region_id != RegionId::new(2, 1));
+    assert_eq!(region_ids, engine.region_ids());
+}
+
+#[tokio::test]
+async fn test_engine_create_regions_partial_failure() {
+    let env = TestEnv::new("create-regions-partial");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let mut requests: Vec<_> = (0..3)
+        .map(|i| {
+            CreateRequestBuilder::new(RegionId::new(1, i))
+                .region_dir(&format!("region-{i}"))
+                .build()
+        })
+        .collect();
+    // The time index of the second region isn't a timestamp.
+    let time_index = requests[1]
+        .column_metadatas
+        .iter_mut()
+        .find(|column| column.semantic_type == SemanticType::Timestamp)
+        .unwrap();
+    time_index.column_schema.data_type = ConcreteDataType::string_datatype();
+
+    let results = engine.create_regions(requests).await.unwrap();
+    assert!(results[0].is_ok());
+    assert!(
+        matches!(results[1], Err(Error::InvalidMeta { .. })),
+        "unexpected result: {:?}",
+        results[1]
+    );
+    assert!(results[2].is_ok());
+    assert!(engine.is_region_exists(RegionId::new(1, 0)));
+    assert!(!engine.is_region_exists(RegionId::new(1, 1)));
+    assert!(engine.is_region_exists(RegionId::new(1, 2)));
+}
+
+#[tokio::test]
+async fn test_engine_worker_busy() {
+    let env = TestEnv::new("worker-busy");
+    let engine = env
+        .create_engine(MitoConfig {
+            worker_channel_size: 1,
+            ..Default::default()
+        })
+        .await;
+
+    // Submits all requests before the worker can drain its channel.
+    let results = join_all((0..100).map(|i| {
+        let request = CreateRequestBuilder::new(RegionId::new(1, i))
+            .region_dir(&format!("region-{i}"))
+            .build();
+        engine.create_region(request)
+    }))
+    .await;
+    let mut num_busy = 0;
+    for (i, res) in results.iter().enumerate() {
+        match res {
+            Ok(()) => assert!(engine.is_region_exists(RegionId::new(1, i as u32))),
+            Err(err) => {
+                assert!(
+                    matches!(err, Error::WorkerBusy { .. }),
+                    "unexpected err: {err}"
+                );
+                assert!(err.status_code().is_retryable());
+                assert!(!engine.is_region_exists(RegionId::new(1, i as u32)));
+                num_busy += 1;
+            }
+        }
+    }
+    assert!(num_busy > 0);
+
+    // Batch creation waits for the worker instead of failing.
+    let requests = (0..100)
+        .map(|i| {
+            CreateRequestBuilder::new(RegionId::new(2, i))
+                .region_dir(&format!("region-2-{i}"))
+                .build()
+        })
+        .collect();
+    let results = engine.create_regions(requests).await.unwrap();
+    assert!(results.iter().all(|res| res.is_ok()));
+}
+
+#[tokio::test]
+async fn test_engine_region_schema() {
+    let env = TestEnv::new("region-schema");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let region_id = RegionId::new(1, 1);
+    let err = engine.region_schema(region_id).unwrap_err();
+    assert!(
+        matches!(err, Error::RegionNotFound { .. }),
+        "unexpected err: {err}"
+    );
+
+    let request = CreateRequestBuilder::new(region_id).tag_num(2).build();
+    engine.create_region(request).await.unwrap();
+
+    let schema = engine.region_schema(region_id).unwrap();
+    let names: Vec<_> = schema
+        .column_schemas()
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect();
+    assert_eq!(&["tag_0", "tag_1", "field_0", "ts"], &names[..]);
+}
+
+#[tokio::test]
+async fn test_engine_create_custom_time_index() {
+    let env = TestEnv::new("custom-time-index");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let region_id = RegionId::new(1, 1);
+    let request = CreateRequestBuilder::new(region_id)
+        .ts_name("event_time")
+        .build();
+    engine.create_region(request).await.unwrap();
+
+    let schema = engine.region_schema(region_id).unwrap();
+    let names: Vec<_> = schema
+        .column_schemas()
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect();
+    assert_eq!(&["tag_0", "field_0", "event_time"], &names[..]);
+
+    // The time index must be a timestamp.
+    let mut request = CreateRequestBuilder::new(RegionId::new(1, 2))
+        .ts_name("event_time")
+        .build();
+    let time_index = request
+        .column_metadatas
+        .iter_mut()
+        .find(|column| column.semantic_type == SemanticType::Timestamp)
+        .unwrap();
+    time_index.column_schema.data_type = ConcreteDataType::string_datatype();
+    let err = engine.create_region(request).await.unwrap_err();
+    assert!(
+        matches!(err, Error::InvalidMeta { .. }),
+        "unexpected err: {err}"
+    );
+}
+
+#[tokio::test]
+async fn test_engine_close_reopen_region() {
+    let env = TestEnv::new("close-reopen");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let region_id = RegionId::new(1, 1);
+    let request = CreateRequestBuilder::new(region_id)
+        .region_dir("region-1")
+        .tag_num(2)
+        .build();
+    engine.create_region(request).await.unwrap();
+    let schema = engine.region_schema(region_id).unwrap();
+
+    engine.close_region(region_id).await.unwrap();
+    assert!(!engine.is_region_exists(region_id));
+
+    engine
+        .open_region(OpenRequest {
+            region_id,
+            region_dir: "region-1".to_string(),
+            options: RegionOptions::default(),
+        })
+        .await
+        .unwrap();
+    assert!(engine.is_region_exists(region_id));
+    assert_eq!(schema, engine.region_schema(region_id).unwrap());
+
+    // Open the same region again.
+    engine
+        .open_region(OpenRequest {
+            region_id,
+            region_dir: "region-1".to_string(),
+            options: RegionOptions::default(),
+        })
+        .await
+        .unwrap();
+    assert!(engine.is_region_exists(region_id));
+}
+
+#[tokio::test]
+async fn test_engine_close_nonexistent_region() {
+    let env = TestEnv::new("close-nonexistent");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let err = engine.close_region(RegionId::new(1, 1)).await.unwrap_err();
+    assert!(
+        matches!(err, Error::RegionNotFound { .. }),
+        "unexpected err: {err}"
+    );
+}
+
+#[tokio::test]
+async fn test_engine_drop_region() {
+    let env = TestEnv::new("drop-region");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let region_id = RegionId::new(1, 1);
+    let err = engine.drop_region(region_id).await.unwrap_err();
+    assert!(
+        matches!(err, Error::RegionNotFound { .. }),
+        "unexpected err: {err}"
+    );
+
+    let request = CreateRequestBuilder::new(region_id)
+        .region_dir("region-1")
+        .build();
+    engine.create_region(request).await.unwrap();
+    let manifest_path = env.data_path().join("region-1").join("manifest");
+    assert!(manifest_path.exists());
+
+    engine.drop_region(region_id).await.unwrap();
+    assert!(!engine.is_region_exists(region_id));
+    assert!(!manifest_path.exists());
+
+    // The dropped region can't be opened again.
+    let err = engine
+        .open_region(OpenRequest {
+            region_id,
+            region_dir: "region-1".to_string(),
+            options: RegionOptions::default(),
+        })
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::InitialMetadata { .. }),
+        "unexpected err: {err}"
+    );
+}
+
+fn new_add_field_request(region_id: RegionId, name: &str, column_id: u32) -> AlterRequest {
+    let column_schema = ColumnSchema::new(name, ConcreteDataType::float64_datatype(), false)
+        .with_default_constraint(Some(ColumnDefaultConstraint::Value(Value::Float64(
+            0.0.into(),
+        ))))
+        .unwrap();
+    AlterRequest {
+        region_id,
+        kind: AlterKind::AddColumns {
+            columns: vec![ColumnMetadata {
+                column_schema,
+                semantic_type: SemanticType::Field,
+                column_id,
+            }],
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_engine_alter_add_column() {
+    let env = TestEnv::new("alter-add-column");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let region_id = RegionId::new(1, 1);
+    let request = CreateRequestBuilder::new(region_id)
+        .region_dir("region-1")
+        .build();
+    engine.create_region(request).await.unwrap();
+
+    // The builder assigns column ids 0, 1, 2.
+    engine
+        .alter_region(new_add_field_request(region_id, "field_1", 3))
+        .await
+        .unwrap();
+    let schema = engine.region_schema(region_id).unwrap();
+    let names: Vec<_> = schema
+        .column_schemas()
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect();
+    assert_eq!(&["tag_0", "field_0", "ts", "field_1"], &names[..]);
+
+    // The altered schema survives reopening the region.
+    engine.close_region(region_id).await.unwrap();
+    engine
+        .open_region(OpenRequest {
+            region_id,
+            region_dir: "region-1".to_string(),
+            options: RegionOptions::default(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(schema, engine.region_schema(region_id).unwrap());
+}
+
+#[tokio::test]
+async fn test_engine_rename_region() {
+    let env = TestEnv::new("rename-region");
+    let engine = env
+        .create_engine(MitoConfig {
+            num_workers: 4,
+            ..Default::default()
+        })
+        .await;
+
+    let region_id = RegionId::new(1, 1);
+    let request = CreateRequestBuilder::new(region_id)
+        .region_dir("region-1")
+        .build();
+    engine.create_region(request).await.unwrap();
+    engine
+        .alter_region(new_add_field_request(region_id, "field_1", 10))
+        .await
+        .unwrap();
+    let schema = engine.region_schema(region_id).unwrap();
+
+    let new_region_id = RegionId::new(2, 1);
+    engine
+        .rename_region(region_id, new_region_id)
+        .await
+        .unwrap();
+    assert!(!engine.is_region_exists(region_id));
+    assert!(engine.is_region_exists(new_region_id));
+    assert_eq!(schema, engine.region_schema(new_region_id).unwrap());
+
+    // The new id is persisted in the manifest.
+    engine.close_region(new_region_id).await.unwrap();
+    engine
+        .open_region(OpenRequest {
+            region_id: new_region_id,
+            region_dir: "region-1".to_string(),
+            options: RegionOptions::default(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(schema, engine.region_schema(new_region_id).unwrap());
+    let err = engine
+        .open_region(OpenRequest {
+            region_id,
+            region_dir: "region-1".to_string(),
+            options: RegionOptions::default(),
+        })
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::InvalidMeta { .. }),
+        "unexpected err: {err}"
+    );
+}
+
+#[tokio::test]
+async fn test_engine_rename_invalid_region() {
+    let env = TestEnv::new("rename-invalid");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let err = engine
+        .rename_region(RegionId::new(1, 1), RegionId::new(2, 1))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::RegionNotFound { .. }),
+        "unexpected err: {err}"
+    );
+
+    for (i, region_id) in [RegionId::new(1, 1), RegionId::new(2, 1)]
+        .into_iter()
+        .enumerate()
+    {
+        let request = CreateRequestBuilder::new(region_id)
+            .region_dir(&format!("region-{i}"))
+            .build();
+        engine.create_region(request).await.unwrap();
+    }
+    let schema = engine.region_schema(RegionId::new(1, 1)).unwrap();
+    // The region is reopened under the old id after failing to rename.
+    let err = engine
+        .rename_region(RegionId::new(1, 1), RegionId::new(2, 1))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::RegionExists { .. }),
+        "unexpected err: {err}"
+    );
+    assert!(engine.is_region_exists(RegionId::new(1, 1)));
+    assert!(engine.is_region_exists(RegionId::new(2, 1)));
+    assert_eq!(schema, engine.region_schema(RegionId::new(1, 1)).unwrap());
+
+    let err = engine
+        .rename_region(RegionId::new(1, 1), RegionId::new(1, 1))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::InvalidRequest { .. }),
+        "unexpected err: {err}"
+    );
+    assert!(engine.is_region_exists(RegionId::new(1, 1)));
+
+    // The manifest still has the old id.
+    engine.close_region(RegionId::new(1, 1)).await.unwrap();
+    engine
+        .open_region(OpenRequest {
+            region_id: RegionId::new(1, 1),
+            region_dir: "region-0".to_string(),
+            options: RegionOptions::default(),
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_engine_rename_region_with_concurrent_open() {
+    let env = TestEnv::new("rename-concurrent-open");
+    let engine = env
+        .create_engine(MitoConfig {
+            num_workers: 4,
+            ..Default::default()
+        })
+        .await;
+
+    let region_id = RegionId::new(1, 1);
+    let request = CreateRequestBuilder::new(region_id)
+        .region_dir("region-1")
+        .build();
+    engine.create_region(request).await.unwrap();
+
+    // The open request is sent while the region is closed for renaming.
+    let new_region_id = RegionId::new(2, 1);
+    let (rename, open) = futures::join!(
+        engine.rename_region(region_id, new_region_id),
+        engine.open_region(OpenRequest {
+            region_id,
+            region_dir: "region-1".to_string(),
+            options: RegionOptions::default(),
+        })
+    );
+    rename.unwrap();
+    let err = open.unwrap_err();
+    assert!(
+        matches!(err, Error::InvalidMeta { .. }),
+        "unexpected err: {err}"
+    );
+    assert!(!engine.is_region_exists(region_id));
+    assert!(engine.is_region_exists(new_region_id));
+}
+
+#[tokio::test]
+async fn test_engine_alter_add_invalid_column() {
+    let env = TestEnv::new("alter-invalid-column");
+    let engine = env.create_engine(MitoConfig::default()).await;
+
+    let region_id = RegionId::new(1, 1);
+    let err = engine
+        .alter_region(new_add_field_request(region_id, "field_1", 3))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::RegionNotFound { .. }),
+        "unexpected err: {err}"
+    );
+
+    let request = CreateRequestBuilder::new(region_id).build();
+    engine.create_region(request).await.unwrap();
+
+    let err = engine
+        .alter_region(new_add_field_request(region_id, "field_0", 3))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::ColumnExists { .. }),
+        "unexpected err: {err}"
+    );
+
+    // A NOT NULL column without default value.
+    let request = AlterRequest {
+        region_id,
+        kind: AlterKind::AddColumns {
+            columns: vec![ColumnMetadata {
+                column_schema: ColumnSchema::new(
+                    "field_1",
+                    ConcreteDataType::float64_datatype(),
+                    false,
+                ),
+                semantic_type: SemanticType::Field,
+                column_id: 3,
+            }],
+        },
+    };
+    let err = engine.alter_region(request).await.unwrap_err();
+    assert!(
+        matches!(err, Error::InvalidMeta { .. }),
+        "unexpected err: {err}"
+    );
+}
@@ -92,6 +92,9 @@ pub enum Error {
     #[snafu(display("Worker {} is stopped, location: {}", id, location))]
     WorkerStopped { id: WorkerId, location: Location },
 
+    #[snafu(display("Worker {} is busy, location: {}", id, location))]
+    WorkerBusy { id: WorkerId, location: Location },
+
     #[snafu(display("Failed to recv result, location: {}, source: {}", location, source))]
     Recv {
         source: tokio::sync::oneshot::error::RecvError,
@@ -119,6 +122,43 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display(
+        "Invalid request to region {}, {}, location: {}",
+        region_id,
+        reason,
+        location
+    ))]
+    InvalidRequest {
+        region_id: RegionId,
+        reason: String,
+        location: Location,
+    },
+
+    #[snafu(display("Region {} not found, location: {}", region_id, location))]
+    RegionNotFound {
+        region_id: RegionId,
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Column {} already exists in region {}, location: {}",
+        column,
+        region_id,
+        location
+    ))]
+    ColumnExists {
+        column: String,
+        region_id: RegionId,
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Manifest file {} is different from the written content after read-back, location: {}",
+        path,
+        location
+    ))]
+    ManifestVerification { path: String, location: Location },
+
     #[snafu(display(
         "Failed to create RecordBatch from vectors, location: {}, source: {}",
         location,
@@ -171,9 +211,10 @@ impl ErrorExt for Error {
         use Error::*;
 
         match self {
-            OpenDal { .. } | WriteParquet { .. } | ReadParquet { .. } => {
-                StatusCode::StorageUnavailable
-            }
+            OpenDal { .. }
+            | WriteParquet { .. }
+            | ReadParquet { .. }
+            | ManifestVerification { .. } => StatusCode::StorageUnavailable,
             CompressObject { .. }
             | DecompressObject { .. }
             | SerdeJson { .. }
@@ -183,10 +224,14 @@ impl ErrorExt for Error {
             InvalidScanIndex { .. }
             | InitialMetadata { .. }
             | InvalidMeta { .. }
-            | InvalidSchema { .. } => StatusCode::InvalidArguments,
+            | InvalidSchema { .. }
+            | RegionNotFound { .. }
+            | InvalidRequest { .. }
+            | ColumnExists { .. } => StatusCode::InvalidArguments,
             RegionMetadataNotFound { .. } | Join { .. } | WorkerStopped { .. } | Recv { .. } => {
                 StatusCode::Internal
             }
+            WorkerBusy { .. } => StatusCode::RuntimeResourcesExhausted,
             WriteBuffer { source, .. } => source.status_code(),
         }
     }
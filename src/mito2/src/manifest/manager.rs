@@ -80,7 +80,8 @@ impl RegionManifestManagerInner {
             &options.manifest_dir,
             options.object_store.clone(),
             options.compress_type,
-        );
+        )
+        .with_verify_writes(options.verify_writes);
 
         // recover from storage
         // construct manifest builder
@@ -104,6 +105,8 @@ impl RegionManifestManagerInner {
         // apply actions from storage
         let mut action_iter = store.scan(version, MAX_VERSION).await?;
         while let Some((manifest_version, raw_action_list)) = action_iter.next_log().await? {
+            // The next delta file should be written after the last one we found.
+            version = version.max(manifest_version + 1);
             let action_list = RegionMetaActionList::decode(&raw_action_list)?;
             for action in action_list.actions {
                 match action {
@@ -127,12 +130,18 @@ impl RegionManifestManagerInner {
                 .take()
                 .context(InitialMetadataSnafu)?;
             info!("Creating region manifest with metadata {:?}", metadata);
+            // Persists the initial metadata so the region can be opened later.
+            let action_list =
+                RegionMetaActionList::with_action(RegionMetaAction::Change(RegionChange {
+                    metadata: metadata.clone(),
+                }));
+            store.save(version, &action_list.encode()?).await?;
+            version += 1;
             manifest_builder.apply_change(RegionChange { metadata });
         }
 
         let manifest = manifest_builder.try_build()?;
         debug!("Recovered region manifest: {:?}", manifest);
-        let version = manifest.version.manifest_version;
 
         // todo: start gc task
 
@@ -336,10 +345,49 @@ mod test {
             }));
         action_list.set_prev_version(0);
 
+        // The initial metadata takes the first version.
         let prev_version = manager.update(action_list).await.unwrap();
-        assert_eq!(prev_version, 0);
+        assert_eq!(prev_version, 1);
 
         let manifest = manager.manifest();
         assert_eq!(manifest.metadata, new_metadata);
     }
+
+    #[tokio::test]
+    async fn recover_manifest_manager() {
+        let metadata = basic_region_metadata();
+        let env = TestEnv::new("");
+        let manager = env
+            .create_manifest_manager(CompressionType::Uncompressed, 10, Some(metadata.clone()))
+            .await
+            .unwrap();
+
+        let mut new_metadata_builder = RegionMetadataBuilder::from_existing(metadata, 1);
+        new_metadata_builder.push_column_metadata(ColumnMetadata {
+            column_schema: ColumnSchema::new("val2", ConcreteDataType::float64_datatype(), false),
+            semantic_type: SemanticType::Field,
+            column_id: 252,
+        });
+        let new_metadata = new_metadata_builder.build().unwrap();
+        let action_list =
+            RegionMetaActionList::with_action(RegionMetaAction::Change(RegionChange {
+                metadata: new_metadata.clone(),
+            }));
+        manager.update(action_list).await.unwrap();
+        manager.stop().await.unwrap();
+
+        // Recovers the manifest without initial metadata.
+        let manager = env
+            .create_manifest_manager(CompressionType::Uncompressed, 10, None)
+            .await
+            .unwrap();
+        assert_eq!(manager.manifest().metadata, new_metadata);
+
+        // New changes must not overwrite existing delta files.
+        let action_list =
+            RegionMetaActionList::with_action(RegionMetaAction::Change(RegionChange {
+                metadata: new_metadata,
+            }));
+        assert_eq!(manager.update(action_list).await.unwrap(), 2);
+    }
 }
@@ -26,6 +26,8 @@ pub struct RegionManifestOptions {
     pub compress_type: CompressionType,
     /// Interval of version ([ManifestVersion](store_api::manifest::ManifestVersion)) between two checkpoints.
     pub checkpoint_interval: u64,
+    /// Whether to read back and verify manifest files after writing them.
+    pub verify_writes: bool,
     /// Initial [RegionMetadata](crate::metadata::RegionMetadata) of this region.
     /// Only need to set when create a new region, otherwise it will be ignored.
     // TODO(yingwen): Could we pass RegionMetadataRef?
@@ -27,8 +27,8 @@ use snafu::{ensure, ResultExt};
 use store_api::manifest::ManifestVersion;
 
 use crate::error::{
-    CompressObjectSnafu, DecompressObjectSnafu, InvalidScanIndexSnafu, OpenDalSnafu, Result,
-    SerdeJsonSnafu, Utf8Snafu,
+    CompressObjectSnafu, DecompressObjectSnafu, InvalidScanIndexSnafu, ManifestVerificationSnafu,
+    OpenDalSnafu, Result, SerdeJsonSnafu, Utf8Snafu,
 };
 
 lazy_static! {
@@ -134,6 +134,8 @@ pub struct ManifestObjectStore {
     object_store: ObjectStore,
     compress_type: CompressionType,
     path: String,
+    /// Reads back every written file and compares it with the written content.
+    verify_writes: bool,
 }
 
 impl ManifestObjectStore {
@@ -142,9 +144,16 @@ impl ManifestObjectStore {
             object_store,
             compress_type,
             path: util::normalize_dir(path),
+            verify_writes: false,
         }
     }
 
+    /// Sets whether to read back and verify files after writing them.
+    pub fn with_verify_writes(mut self, verify_writes: bool) -> Self {
+        self.verify_writes = verify_writes;
+        self
+    }
+
     /// Returns the delta file path under the **current** compression algorithm
     fn delta_file_path(&self, version: ManifestVersion) -> String {
         gen_path(&self.path, &delta_file(version), self.compress_type)
@@ -183,6 +192,27 @@ impl ManifestObjectStore {
         &self.path
     }
 
+    /// Writes `data` to `path`, then reads it back and compares the content
+    /// if `verify_writes` is enabled.
+    async fn write_file(&self, path: &str, data: Vec<u8>) -> Result<()> {
+        if !self.verify_writes {
+            return self
+                .object_store
+                .write(path, data)
+                .await
+                .context(OpenDalSnafu);
+        }
+
+        self.object_store
+            .write(path, data.clone())
+            .await
+            .context(OpenDalSnafu)?;
+        let written = self.object_store.read(path).await.context(OpenDalSnafu)?;
+        ensure!(written == data, ManifestVerificationSnafu { path });
+
+        Ok(())
+    }
+
     pub async fn scan(
         &self,
         start: ManifestVersion,
@@ -334,10 +364,7 @@ impl ManifestObjectStore {
                 compress_type: self.compress_type,
                 path: &path,
             })?;
-        self.object_store
-            .write(&path, data)
-            .await
-            .context(OpenDalSnafu)
+        self.write_file(&path, data).await
     }
 
     async fn delete(&self, start: ManifestVersion, end: ManifestVersion) -> Result<()> {
@@ -381,10 +408,7 @@ impl ManifestObjectStore {
                 compress_type: self.compress_type,
                 path: &path,
             })?;
-        self.object_store
-            .write(&path, data)
-            .await
-            .context(OpenDalSnafu)?;
+        self.write_file(&path, data).await?;
 
         // Because last checkpoint file only contain size and version, which is tiny, so we don't compress it.
         let last_checkpoint_path = self.last_checkpoint_path();
@@ -403,10 +427,7 @@ impl ManifestObjectStore {
         );
 
         let bytes = checkpoint_metadata.encode()?;
-        self.object_store
-            .write(&last_checkpoint_path, bytes)
-            .await
-            .context(OpenDalSnafu)?;
+        self.write_file(&last_checkpoint_path, bytes).await?;
 
         Ok(())
     }
@@ -542,10 +563,12 @@ impl CheckpointMetadata {
 #[cfg(test)]
 mod tests {
     use common_test_util::temp_dir::create_temp_dir;
-    use object_store::services::Fs;
+    use object_store::services::{Fs, Memory};
+    use object_store::test_util::CorruptWriteLayer;
     use object_store::ObjectStore;
 
     use super::*;
+    use crate::error::Error;
 
     fn new_test_manifest_store() -> ManifestObjectStore {
         common_telemetry::init_default_ut_logging();
@@ -712,4 +735,36 @@ mod tests {
         let mut it = log_store.scan(0, 10).await.unwrap();
         assert!(it.next_log().await.unwrap().is_none());
     }
+
+    #[tokio::test]
+    async fn test_verify_manifest_writes() {
+        let object_store = ObjectStore::new(Memory::default())
+            .unwrap()
+            .layer(CorruptWriteLayer)
+            .finish();
+        let log_store = ManifestObjectStore::new("/", object_store, CompressionType::Uncompressed);
+
+        // The corruption is unnoticed without verification.
+        log_store.save(0, "hello, 0".as_bytes()).await.unwrap();
+
+        let log_store = log_store.with_verify_writes(true);
+        let err = log_store.save(1, "hello, 1".as_bytes()).await.unwrap_err();
+        assert!(matches!(err, Error::ManifestVerification { .. }), "{err}");
+        let err = log_store
+            .save_checkpoint(1, "checkpoint".as_bytes())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ManifestVerification { .. }), "{err}");
+
+        // Verified writes succeed on a healthy backend.
+        let log_store = new_test_manifest_store().with_verify_writes(true);
+        log_store.save(0, "hello, 0".as_bytes()).await.unwrap();
+        log_store
+            .save_checkpoint(0, "checkpoint".as_bytes())
+            .await
+            .unwrap();
+        let (v, checkpoint) = log_store.load_last_checkpoint().await.unwrap().unwrap();
+        assert_eq!(0, v);
+        assert_eq!(checkpoint, "checkpoint".as_bytes());
+    }
 }
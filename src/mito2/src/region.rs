@@ -22,7 +22,9 @@ use std::sync::{Arc, RwLock};
 
 use store_api::storage::RegionId;
 
+use crate::error::Result;
 use crate::manifest::manager::RegionManifestManager;
+use crate::metadata::RegionMetadataRef;
 use crate::region::version::VersionControlRef;
 
 /// Type to store region version.
@@ -38,13 +40,28 @@ pub(crate) struct MitoRegion {
     pub(crate) region_id: RegionId,
 
     /// Version controller for this region.
-    version_control: VersionControlRef,
+    pub(crate) version_control: VersionControlRef,
     /// Manager to maintain manifest for this region.
-    manifest_manager: RegionManifestManager,
+    pub(crate) manifest_manager: RegionManifestManager,
+    /// Data directory of the region.
+    pub(crate) region_dir: String,
 }
 
 pub(crate) type MitoRegionRef = Arc<MitoRegion>;
 
+impl MitoRegion {
+    /// Returns current metadata of the region.
+    pub(crate) fn metadata(&self) -> RegionMetadataRef {
+        let version = self.version_control.current();
+        version.metadata.clone()
+    }
+
+    /// Stops background tasks of the region.
+    pub(crate) async fn stop(&self) -> Result<()> {
+        self.manifest_manager.stop().await
+    }
+}
+
 /// Regions indexed by ids.
 #[derive(Debug, Default)]
 pub(crate) struct RegionMap {
@@ -63,6 +80,24 @@ impl RegionMap {
         let mut regions = self.regions.write().unwrap();
         regions.insert(region.region_id, region);
     }
+
+    /// Removes region by region id and returns the removed region.
+    pub(crate) fn remove_region(&self, region_id: RegionId) -> Option<MitoRegionRef> {
+        let mut regions = self.regions.write().unwrap();
+        regions.remove(&region_id)
+    }
+
+    /// Gets region by region id.
+    pub(crate) fn get_region(&self, region_id: RegionId) -> Option<MitoRegionRef> {
+        let regions = self.regions.read().unwrap();
+        regions.get(&region_id).cloned()
+    }
+
+    /// Returns ids of all regions in the map.
+    pub(crate) fn region_ids(&self) -> Vec<RegionId> {
+        let regions = self.regions.read().unwrap();
+        regions.keys().copied().collect()
+    }
 }
 
 pub(crate) type RegionMapRef = Arc<RegionMap>;
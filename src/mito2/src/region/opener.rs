@@ -18,61 +18,88 @@ use std::sync::Arc;
 
 use object_store::util::join_dir;
 use object_store::ObjectStore;
+use snafu::ensure;
+use store_api::storage::RegionId;
 
 use crate::config::MitoConfig;
-use crate::error::Result;
+use crate::error::{InvalidMetaSnafu, Result};
+use crate::manifest::action::{RegionChange, RegionMetaAction, RegionMetaActionList};
 use crate::manifest::manager::RegionManifestManager;
 use crate::manifest::options::RegionManifestOptions;
 use crate::memtable::MemtableBuilderRef;
-use crate::metadata::RegionMetadata;
+use crate::metadata::{RegionMetadata, RegionMetadataBuilder};
 use crate::region::version::{VersionBuilder, VersionControl};
 use crate::region::MitoRegion;
 
 /// Builder to create a new [MitoRegion] or open an existing one.
 pub(crate) struct RegionOpener {
-    metadata: RegionMetadata,
+    region_id: RegionId,
+    metadata: Option<RegionMetadata>,
     memtable_builder: MemtableBuilderRef,
     object_store: ObjectStore,
     region_dir: String,
+    renamed_from: Option<RegionId>,
 }
 
 impl RegionOpener {
     /// Returns a new opener.
     pub(crate) fn new(
-        metadata: RegionMetadata,
+        region_id: RegionId,
         memtable_builder: MemtableBuilderRef,
         object_store: ObjectStore,
     ) -> RegionOpener {
         RegionOpener {
-            metadata,
+            region_id,
+            metadata: None,
             memtable_builder,
             object_store,
             region_dir: String::new(),
+            renamed_from: None,
         }
     }
 
+    /// Sets metadata of the region to create.
+    pub(crate) fn metadata(mut self, metadata: RegionMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
     /// Sets the region dir.
     pub(crate) fn region_dir(mut self, value: &str) -> Self {
         self.region_dir = value.to_string();
         self
     }
 
+    /// Opens a region whose manifest has id `region_id` and renames it to the id
+    /// of the opener.
+    pub(crate) fn renamed_from(mut self, region_id: RegionId) -> Self {
+        self.renamed_from = Some(region_id);
+        self
+    }
+
     /// Writes region manifest and creates a new region.
+    ///
+    /// # Panics
+    /// Panics if metadata is not set.
     pub(crate) async fn create(self, config: &MitoConfig) -> Result<MitoRegion> {
-        let region_id = self.metadata.region_id;
+        let region_id = self.region_id;
+        let metadata = self
+            .metadata
+            .expect("metadata must be set to create a region");
         // Create a manifest manager for this region.
         let options = RegionManifestOptions {
             manifest_dir: new_manifest_dir(&self.region_dir),
             object_store: self.object_store,
             compress_type: config.manifest_compress_type,
             checkpoint_interval: config.manifest_checkpoint_interval,
+            verify_writes: config.verify_manifest_writes,
             // We are creating a new region, so we need to set this field.
-            initial_metadata: Some(self.metadata.clone()),
+            initial_metadata: Some(metadata.clone()),
         };
         // Writes regions to the manifest file.
         let manifest_manager = RegionManifestManager::new(options).await?;
 
-        let metadata = Arc::new(self.metadata);
+        let metadata = Arc::new(metadata);
         let mutable = self.memtable_builder.build(&metadata);
 
         let version = VersionBuilder::new(metadata, mutable).build();
@@ -82,8 +109,70 @@ impl RegionOpener {
             region_id,
             version_control,
             manifest_manager,
+            region_dir: self.region_dir,
         })
     }
+
+    /// Opens an existing region and recovers its metadata from the manifest.
+    ///
+    /// If the region is renamed, the new id is persisted to the manifest after
+    /// everything else succeeds, so the manifest keeps the old id on failure.
+    pub(crate) async fn open(self, config: &MitoConfig) -> Result<MitoRegion> {
+        let options = RegionManifestOptions {
+            manifest_dir: new_manifest_dir(&self.region_dir),
+            object_store: self.object_store,
+            compress_type: config.manifest_compress_type,
+            checkpoint_interval: config.manifest_checkpoint_interval,
+            verify_writes: config.verify_manifest_writes,
+            // The region must already have a manifest.
+            initial_metadata: None,
+        };
+        let manifest_manager = RegionManifestManager::new(options).await?;
+        let manifest = manifest_manager.manifest();
+        let expect_region_id = self.renamed_from.unwrap_or(self.region_id);
+        ensure!(
+            manifest.metadata.region_id == expect_region_id,
+            InvalidMetaSnafu {
+                reason: format!(
+                    "region id in manifest is {}, expect {}",
+                    manifest.metadata.region_id, expect_region_id
+                ),
+            }
+        );
+
+        let mut metadata = manifest.metadata.clone();
+        if self.renamed_from.is_some() {
+            metadata = rename_metadata(&metadata, self.region_id)?;
+            let action_list =
+                RegionMetaActionList::with_action(RegionMetaAction::Change(RegionChange {
+                    metadata: metadata.clone(),
+                }));
+            manifest_manager.update(action_list).await?;
+        }
+
+        let metadata = Arc::new(metadata);
+        let mutable = self.memtable_builder.build(&metadata);
+
+        let version = VersionBuilder::new(metadata, mutable).build();
+        let version_control = Arc::new(VersionControl::new(version));
+
+        Ok(MitoRegion {
+            region_id: self.region_id,
+            version_control,
+            manifest_manager,
+            region_dir: self.region_dir,
+        })
+    }
+}
+
+/// Returns a copy of `metadata` with id `region_id` and a new version.
+fn rename_metadata(metadata: &RegionMetadata, region_id: RegionId) -> Result<RegionMetadata> {
+    let mut builder = RegionMetadataBuilder::new(region_id, metadata.version + 1);
+    for column in &metadata.column_metadatas {
+        builder.push_column_metadata(column.clone());
+    }
+    builder.primary_key(metadata.primary_key.clone());
+    builder.build()
 }
 
 /// Returns the directory to the manifest files.
@@ -30,7 +30,7 @@ use store_api::manifest::ManifestVersion;
 use store_api::storage::SequenceNumber;
 
 use crate::memtable::version::{MemtableVersion, MemtableVersionRef};
-use crate::memtable::MemtableRef;
+use crate::memtable::{MemtableBuilderRef, MemtableRef};
 use crate::metadata::RegionMetadataRef;
 use crate::sst::version::{SstVersion, SstVersionRef};
 
@@ -48,10 +48,35 @@ impl VersionControl {
             version: ArcSwap::new(Arc::new(version)),
         }
     }
+
+    /// Returns current [Version].
+    pub(crate) fn current(&self) -> VersionRef {
+        self.version.load_full()
+    }
+
+    /// Alters schema of the region to `metadata`.
+    ///
+    /// It replaces memtables of current version with a new mutable memtable
+    /// built by `builder`, so callers must ensure memtables are empty.
+    pub(crate) fn alter_schema(&self, metadata: RegionMetadataRef, builder: &MemtableBuilderRef) {
+        let mutable = builder.build(&metadata);
+        let version = self.current();
+        let new_version = Arc::new(Version {
+            metadata,
+            memtables: Arc::new(MemtableVersion::new(mutable)),
+            ssts: version.ssts.clone(),
+            flushed_sequence: version.flushed_sequence,
+            manifest_version: version.manifest_version,
+        });
+
+        self.version.store(new_version);
+    }
 }
 
 pub(crate) type VersionControlRef = Arc<VersionControl>;
 
+pub(crate) type VersionRef = Arc<Version>;
+
 /// Static metadata of a region.
 #[derive(Clone, Debug)]
 pub(crate) struct Version {
@@ -59,7 +84,7 @@ pub(crate) struct Version {
     ///
     /// Altering metadata isn't frequent, storing metadata in Arc to allow sharing
     /// metadata and reuse metadata when creating a new `Version`.
-    metadata: RegionMetadataRef,
+    pub(crate) metadata: RegionMetadataRef,
     /// Mutable and immutable memtables.
     ///
     /// Wrapped in Arc to make clone of `Version` much cheaper.
@@ -14,6 +14,7 @@
 
 //! Utilities for testing.
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use common_datasource::compression::CompressionType;
@@ -50,6 +51,11 @@ impl TestEnv {
         }
     }
 
+    /// Returns the path to the data of the object store under this env.
+    pub fn data_path(&self) -> PathBuf {
+        self.data_home.path().join("data")
+    }
+
     /// Creates a new engine with specific config under this env.
     pub async fn create_engine(&self, config: MitoConfig) -> MitoEngine {
         let (log_store, object_store) = self.create_log_and_object_store().await;
@@ -95,6 +101,7 @@ impl TestEnv {
             object_store,
             compress_type,
             checkpoint_interval,
+            verify_writes: false,
             initial_metadata,
         };
 
@@ -108,6 +115,7 @@ pub struct CreateRequestBuilder {
     region_dir: String,
     tag_num: usize,
     field_num: usize,
+    ts_name: String,
     create_if_not_exists: bool,
 }
 
@@ -118,6 +126,7 @@ impl Default for CreateRequestBuilder {
             region_dir: "test".to_string(),
             tag_num: 1,
             field_num: 1,
+            ts_name: "ts".to_string(),
             create_if_not_exists: false,
         }
     }
@@ -146,6 +155,12 @@ impl CreateRequestBuilder {
         self
     }
 
+    /// Sets the name of the time index column.
+    pub fn ts_name(mut self, value: &str) -> Self {
+        self.ts_name = value.to_string();
+        self
+    }
+
     pub fn create_if_not_exists(mut self, value: bool) -> Self {
         self.create_if_not_exists = value;
         self
@@ -182,7 +197,7 @@ impl CreateRequestBuilder {
         }
         column_metadatas.push(ColumnMetadata {
             column_schema: ColumnSchema::new(
-                "ts",
+                self.ts_name.clone(),
                 ConcreteDataType::timestamp_millisecond_datatype(),
                 false,
             ),
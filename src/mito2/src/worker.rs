@@ -14,8 +14,12 @@
 
 //! Structs and utilities for writing regions.
 
+mod handle_alter;
+mod handle_close;
 mod handle_create;
+mod handle_drop;
 mod handle_open;
+mod handle_rename;
 pub(crate) mod request;
 
 use std::collections::hash_map::DefaultHasher;
@@ -30,13 +34,14 @@ use object_store::ObjectStore;
 use snafu::{ensure, ResultExt};
 use store_api::logstore::LogStore;
 use store_api::storage::RegionId;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{mpsc, Mutex};
 
 use crate::config::MitoConfig;
-use crate::error::{JoinSnafu, Result, WorkerStoppedSnafu};
+use crate::error::{JoinSnafu, Result, WorkerBusySnafu, WorkerStoppedSnafu};
 use crate::memtable::{DefaultMemtableBuilder, MemtableBuilderRef};
-use crate::region::{RegionMap, RegionMapRef};
+use crate::region::{MitoRegionRef, RegionMap, RegionMapRef};
 use crate::worker::request::{RegionRequest, RequestBody, WorkerRequest};
 
 /// Identifier for a worker.
@@ -119,17 +124,47 @@ impl WorkerGroup {
     }
 
     /// Submit a request to a worker in the group.
+    ///
+    /// Returns [WorkerBusy](crate::error::Error::WorkerBusy) if the worker's
+    /// request channel is full.
     pub(crate) async fn submit_to_worker(&self, request: RegionRequest) -> Result<()> {
         self.worker(request.body.region_id())
             .submit_request(request)
             .await
     }
 
+    /// Sends a request to a worker in the group, waiting until the worker's
+    /// request channel has space.
+    ///
+    /// Used to fan out requests the engine issues itself, so a large batch doesn't
+    /// fail halfway.
+    pub(crate) async fn send_to_worker(&self, request: RegionRequest) -> Result<()> {
+        self.worker(request.body.region_id())
+            .send_request(request)
+            .await
+    }
+
     /// Returns true if the specific region exists.
     pub(crate) fn is_region_exists(&self, region_id: RegionId) -> bool {
         self.worker(region_id).is_region_exists(region_id)
     }
 
+    /// Returns region of specific `region_id`.
+    pub(crate) fn get_region(&self, region_id: RegionId) -> Option<MitoRegionRef> {
+        self.worker(region_id).get_region(region_id)
+    }
+
+    /// Returns ids of all regions in the group, sorted by id.
+    pub(crate) fn region_ids(&self) -> Vec<RegionId> {
+        let mut region_ids: Vec<_> = self
+            .workers
+            .iter()
+            .flat_map(|worker| worker.regions.region_ids())
+            .collect();
+        region_ids.sort_unstable_by_key(|region_id| region_id.as_u64());
+        region_ids
+    }
+
     /// Get worker for specific `region_id`.
     fn worker(&self, region_id: RegionId) -> &RegionWorker {
         let mut hasher = DefaultHasher::new();
@@ -196,7 +231,20 @@ impl RegionWorker {
     }
 
     /// Submit request to background worker thread.
+    ///
+    /// Returns [WorkerBusy](crate::error::Error::WorkerBusy) instead of waiting
+    /// if the request channel is full.
     async fn submit_request(&self, request: RegionRequest) -> Result<()> {
+        ensure!(self.is_running(), WorkerStoppedSnafu { id: self.id });
+        match self.sender.try_send(WorkerRequest::Region(request)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => WorkerBusySnafu { id: self.id }.fail(),
+            Err(TrySendError::Closed(_)) => self.on_exited(),
+        }
+    }
+
+    /// Send request to background worker thread, waiting if the request channel is full.
+    async fn send_request(&self, request: RegionRequest) -> Result<()> {
         ensure!(self.is_running(), WorkerStoppedSnafu { id: self.id });
         if self
             .sender
@@ -204,18 +252,23 @@ impl RegionWorker {
             .await
             .is_err()
         {
-            logging::warn!(
-                "Worker {} is already exited but the running flag is still true",
-                self.id
-            );
-            // Manually set the running flag to false to avoid printing more warning logs.
-            self.set_running(false);
-            return WorkerStoppedSnafu { id: self.id }.fail();
+            return self.on_exited();
         }
 
         Ok(())
     }
 
+    /// Marks the worker as stopped after its thread exited unexpectedly.
+    fn on_exited(&self) -> Result<()> {
+        logging::warn!(
+            "Worker {} is already exited but the running flag is still true",
+            self.id
+        );
+        // Manually set the running flag to false to avoid printing more warning logs.
+        self.set_running(false);
+        WorkerStoppedSnafu { id: self.id }.fail()
+    }
+
     /// Stop the worker.
     ///
     /// This method waits until the worker thread exists.
@@ -249,6 +302,11 @@ impl RegionWorker {
     fn is_region_exists(&self, region_id: RegionId) -> bool {
         self.regions.is_region_exists(region_id)
     }
+
+    /// Returns region of specific `region_id`.
+    fn get_region(&self, region_id: RegionId) -> Option<MitoRegionRef> {
+        self.regions.get_region(region_id)
+    }
 }
 
 impl Drop for RegionWorker {
@@ -367,6 +425,10 @@ impl<S> RegionWorkerLoop<S> {
             let res = match request.body {
                 RequestBody::Create(req) => self.handle_create_request(req).await,
                 RequestBody::Open(req) => self.handle_open_request(req).await,
+                RequestBody::Close(req) => self.handle_close_request(req).await,
+                RequestBody::Drop(req) => self.handle_drop_request(req).await,
+                RequestBody::Alter(req) => self.handle_alter_request(req).await,
+                RequestBody::Rename(req) => self.handle_rename_request(req).await,
                 RequestBody::Write(_) => unreachable!(),
             };
 
@@ -0,0 +1,101 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Handling alter request.
+
+use std::sync::Arc;
+
+use common_telemetry::logging;
+use snafu::{ensure, OptionExt};
+
+use crate::error::{ColumnExistsSnafu, InvalidMetaSnafu, RegionNotFoundSnafu, Result};
+use crate::manifest::action::{RegionChange, RegionMetaAction, RegionMetaActionList};
+use crate::metadata::{ColumnMetadata, RegionMetadata, RegionMetadataBuilder, SemanticType};
+use crate::worker::request::{AlterKind, AlterRequest};
+use crate::worker::RegionWorkerLoop;
+
+impl<S> RegionWorkerLoop<S> {
+    pub(crate) async fn handle_alter_request(&mut self, request: AlterRequest) -> Result<()> {
+        let region = self
+            .regions
+            .get_region(request.region_id)
+            .context(RegionNotFoundSnafu {
+                region_id: request.region_id,
+            })?;
+
+        let metadata = region.metadata();
+        let new_metadata = match request.kind {
+            AlterKind::AddColumns { columns } => add_columns(&metadata, columns)?,
+        };
+
+        // Persists the new metadata before applying it to the region.
+        let action_list =
+            RegionMetaActionList::with_action(RegionMetaAction::Change(RegionChange {
+                metadata: new_metadata.clone(),
+            }));
+        region.manifest_manager.update(action_list).await?;
+
+        logging::info!(
+            "Region {} is altered, schema version {} -> {}",
+            region.region_id,
+            metadata.version,
+            new_metadata.version
+        );
+
+        region
+            .version_control
+            .alter_schema(Arc::new(new_metadata), &self.memtable_builder);
+
+        Ok(())
+    }
+}
+
+/// Returns a new [RegionMetadata] that appends `columns` to `metadata`.
+fn add_columns(metadata: &RegionMetadata, columns: Vec<ColumnMetadata>) -> Result<RegionMetadata> {
+    let mut builder = RegionMetadataBuilder::from_existing(metadata.clone(), metadata.version + 1);
+    let mut primary_key = metadata.primary_key.clone();
+    for column in columns {
+        let name = &column.column_schema.name;
+        ensure!(
+            metadata.schema.column_schema_by_name(name).is_none(),
+            ColumnExistsSnafu {
+                column: name,
+                region_id: metadata.region_id,
+            }
+        );
+        // Existing rows don't have the new column, so it must be able to
+        // fill them.
+        ensure!(
+            column.column_schema.is_nullable()
+                || column.column_schema.default_constraint().is_some(),
+            InvalidMetaSnafu {
+                reason: format!("no default value for new column {}", name),
+            }
+        );
+        ensure!(
+            column.semantic_type != SemanticType::Timestamp,
+            InvalidMetaSnafu {
+                reason: format!("can't add time index column {}", name),
+            }
+        );
+
+        if column.semantic_type == SemanticType::Tag {
+            primary_key.push(column.column_id);
+        }
+        builder.push_column_metadata(column);
+    }
+    builder.primary_key(primary_key);
+
+    builder.build()
+}
@@ -0,0 +1,39 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Handling close request.
+
+use common_telemetry::logging;
+use snafu::OptionExt;
+
+use crate::error::{RegionNotFoundSnafu, Result};
+use crate::worker::request::CloseRequest;
+use crate::worker::RegionWorkerLoop;
+
+impl<S> RegionWorkerLoop<S> {
+    pub(crate) async fn handle_close_request(&mut self, request: CloseRequest) -> Result<()> {
+        let region =
+            self.regions
+                .remove_region(request.region_id)
+                .context(RegionNotFoundSnafu {
+                    region_id: request.region_id,
+                })?;
+
+        region.stop().await?;
+
+        logging::info!("Region {} is closed", region.region_id);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Handling drop request.
+
+use common_telemetry::logging;
+use object_store::util::normalize_dir;
+use snafu::{OptionExt, ResultExt};
+
+use crate::error::{OpenDalSnafu, RegionNotFoundSnafu, Result};
+use crate::worker::request::DropRequest;
+use crate::worker::RegionWorkerLoop;
+
+impl<S> RegionWorkerLoop<S> {
+    pub(crate) async fn handle_drop_request(&mut self, request: DropRequest) -> Result<()> {
+        let region =
+            self.regions
+                .remove_region(request.region_id)
+                .context(RegionNotFoundSnafu {
+                    region_id: request.region_id,
+                })?;
+
+        // The region is already removed from the worker, so we only log
+        // errors below instead of returning them.
+        if let Err(e) = region.stop().await {
+            logging::error!(e; "Failed to stop region {} while dropping it", region.region_id);
+        }
+
+        // Removes the manifest and SSTs under the region dir.
+        let region_dir = normalize_dir(&region.region_dir);
+        if let Err(e) = self
+            .object_store
+            .remove_all(&region_dir)
+            .await
+            .context(OpenDalSnafu)
+        {
+            logging::error!(
+                e; "Failed to remove dir {} of dropped region {}",
+                region_dir,
+                region.region_id
+            );
+        }
+
+        logging::info!("Region {} is dropped", region.region_id);
+
+        Ok(())
+    }
+}
@@ -14,12 +14,36 @@
 
 //! Handling open request.
 
+use std::sync::Arc;
+
+use common_telemetry::logging;
+
 use crate::error::Result;
+use crate::region::opener::RegionOpener;
 use crate::worker::request::OpenRequest;
 use crate::worker::RegionWorkerLoop;
 
 impl<S> RegionWorkerLoop<S> {
-    pub(crate) async fn handle_open_request(&mut self, _request: OpenRequest) -> Result<()> {
-        unimplemented!()
+    pub(crate) async fn handle_open_request(&mut self, request: OpenRequest) -> Result<()> {
+        // Opening an opened region is a no-op.
+        if self.regions.is_region_exists(request.region_id) {
+            return Ok(());
+        }
+
+        // Open the region from its manifest.
+        let region = RegionOpener::new(
+            request.region_id,
+            self.memtable_builder.clone(),
+            self.object_store.clone(),
+        )
+        .region_dir(&request.region_dir)
+        .open(&self.config)
+        .await?;
+
+        logging::info!("Region {} is opened", region.region_id);
+
+        self.regions.insert_region(Arc::new(region));
+
+        Ok(())
     }
 }
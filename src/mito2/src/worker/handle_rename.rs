@@ -0,0 +1,60 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Handling rename request.
+
+use std::sync::Arc;
+
+use common_telemetry::logging;
+use snafu::ensure;
+
+use crate::error::{RegionExistsSnafu, Result};
+use crate::region::opener::RegionOpener;
+use crate::worker::request::RenameRequest;
+use crate::worker::RegionWorkerLoop;
+
+impl<S> RegionWorkerLoop<S> {
+    /// Opens the closed region `old_region_id` under the new id and persists the
+    /// new id to its manifest.
+    ///
+    /// The request is handled by the worker of the new id.
+    pub(crate) async fn handle_rename_request(&mut self, request: RenameRequest) -> Result<()> {
+        ensure!(
+            !self.regions.is_region_exists(request.region_id),
+            RegionExistsSnafu {
+                region_id: request.region_id,
+            }
+        );
+
+        let region = RegionOpener::new(
+            request.region_id,
+            self.memtable_builder.clone(),
+            self.object_store.clone(),
+        )
+        .region_dir(&request.region_dir)
+        .renamed_from(request.old_region_id)
+        .open(&self.config)
+        .await?;
+
+        logging::info!(
+            "Region {} is renamed to {}",
+            request.old_region_id,
+            request.region_id
+        );
+
+        self.regions.insert_region(Arc::new(region));
+
+        Ok(())
+    }
+}
@@ -69,10 +69,58 @@ pub struct CreateRequest {
 pub struct OpenRequest {
     /// Region to open.
     pub region_id: RegionId,
+    /// Data directory of the region.
+    pub region_dir: String,
     /// Options of the created region.
     pub options: RegionOptions,
 }
 
+/// Close region request.
+#[derive(Debug)]
+pub(crate) struct CloseRequest {
+    /// Region to close.
+    pub region_id: RegionId,
+}
+
+/// Drop region request.
+#[derive(Debug)]
+pub(crate) struct DropRequest {
+    /// Region to drop.
+    pub region_id: RegionId,
+}
+
+/// Request to open a closed region under a new id.
+#[derive(Debug)]
+pub(crate) struct RenameRequest {
+    /// New id of the region.
+    pub region_id: RegionId,
+    /// Id of the region in its manifest.
+    pub old_region_id: RegionId,
+    /// Data directory of the region.
+    pub region_dir: String,
+}
+
+/// Alter region request.
+#[derive(Debug)]
+pub struct AlterRequest {
+    /// Region to alter.
+    pub region_id: RegionId,
+    /// Kind of alteration to apply.
+    pub kind: AlterKind,
+}
+
+/// Kind of the alteration.
+#[derive(Debug)]
+pub enum AlterKind {
+    /// Adds columns to the region.
+    ///
+    /// New columns must be nullable or have a default value.
+    AddColumns {
+        /// Columns to add.
+        columns: Vec<ColumnMetadata>,
+    },
+}
+
 /// Request to write a region.
 #[derive(Debug)]
 pub(crate) struct WriteRequest {
@@ -124,6 +172,14 @@ pub(crate) enum RequestBody {
     Create(CreateRequest),
     /// Opens an existing region.
     Open(OpenRequest),
+    /// Closes a region.
+    Close(CloseRequest),
+    /// Drops a region and removes its data.
+    Drop(DropRequest),
+    /// Alters a region.
+    Alter(AlterRequest),
+    /// Renames a region.
+    Rename(RenameRequest),
 }
 
 impl RequestBody {
@@ -133,15 +189,23 @@ impl RequestBody {
             RequestBody::Write(req) => req.region_id,
             RequestBody::Create(req) => req.region_id,
             RequestBody::Open(req) => req.region_id,
+            RequestBody::Close(req) => req.region_id,
+            RequestBody::Drop(req) => req.region_id,
+            RequestBody::Alter(req) => req.region_id,
+            RequestBody::Rename(req) => req.region_id,
         }
     }
 
-    /// Returns whether the request is a DDL (e.g. CREATE/OPEN/ALTER).
+    /// Returns whether the request is a DDL (e.g. CREATE/OPEN/CLOSE/DROP/ALTER/RENAME).
     pub(crate) fn is_ddl(&self) -> bool {
         match self {
             RequestBody::Write(_) => false,
             RequestBody::Create(_) => true,
             RequestBody::Open(_) => true,
+            RequestBody::Close(_) => true,
+            RequestBody::Drop(_) => true,
+            RequestBody::Alter(_) => true,
+            RequestBody::Rename(_) => true,
         }
     }
 }
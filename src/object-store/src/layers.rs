@@ -12,7 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod list_cache;
 mod lru_cache;
+mod ramp_up;
+mod sampled_tracing;
+mod shared_concurrent_limit;
+mod timeout;
 
+pub use list_cache::*;
 pub use lru_cache::*;
+pub use ramp_up::*;
+pub use sampled_tracing::*;
+pub use shared_concurrent_limit::*;
+pub use timeout::*;
 pub use opendal::layers::*;
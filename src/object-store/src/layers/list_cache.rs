@@ -0,0 +1,598 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use opendal::raw::oio::{self, Page};
+use opendal::raw::{
+    Accessor, Layer, LayeredAccessor, OpAppend, OpBatch, OpCopy, OpCreateDir, OpDelete, OpList,
+    OpRead, OpRename, OpWrite, RpAppend, RpBatch, RpCopy, RpCreateDir, RpDelete, RpList, RpRead,
+    RpRename, RpWrite,
+};
+use opendal::Result;
+
+/// A layer that caches list results for a while.
+///
+/// Operations changing objects through the layer, e.g. writes, deletes, copies
+/// and renames, invalidate cached listings of their parent directories, in both
+/// async and blocking APIs. Writes invalidate them again on close, as some
+/// services (e.g. S3) only upload the object on close. Batch operations, such as
+/// removing multiple objects, invalidate all cached listings. A listing that
+/// started before an invalidation isn't cached, as it may miss the change.
+///
+/// Expired listings are evicted when a new listing is cached.
+#[derive(Debug, Clone)]
+pub struct ListCacheLayer {
+    ttl: Duration,
+}
+
+impl ListCacheLayer {
+    /// Returns a new layer caching list results for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl }
+    }
+}
+
+impl<I: Accessor> Layer<I> for ListCacheLayer {
+    type LayeredAccessor = ListCacheAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        ListCacheAccessor {
+            inner,
+            ttl: self.ttl,
+            listings: Listings::default(),
+        }
+    }
+}
+
+/// Key of a cached listing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ListKey {
+    /// Path to list.
+    path: String,
+    /// Debug format of the list options.
+    args: String,
+}
+
+/// A cached listing.
+#[derive(Debug)]
+struct Listing {
+    entries: Vec<oio::Entry>,
+    created: Instant,
+}
+
+#[derive(Debug, Default)]
+struct ListingsInner {
+    listings: HashMap<ListKey, Listing>,
+    /// Bumped on every invalidation.
+    generation: u64,
+}
+
+type Listings = Arc<Mutex<ListingsInner>>;
+
+/// Removes listings that may contain the object at `path`.
+fn invalidate(listings: &Listings, path: &str) {
+    let mut inner = listings.lock().unwrap();
+    inner.listings.retain(|key, _| !path.starts_with(&key.path));
+    inner.generation += 1;
+}
+
+#[derive(Debug)]
+pub struct ListCacheAccessor<I> {
+    inner: I,
+    ttl: Duration,
+    listings: Listings,
+}
+
+impl<I> ListCacheAccessor<I> {
+    /// Returns the cached entries of `key` if they are not expired.
+    fn get(&self, key: &ListKey) -> Option<Vec<oio::Entry>> {
+        let mut inner = self.listings.lock().unwrap();
+        let listings = &mut inner.listings;
+        match listings.get(key) {
+            Some(listing) if listing.created.elapsed() < self.ttl => Some(listing.entries.clone()),
+            Some(_) => {
+                let _ = listings.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Returns the current generation, to be passed to [Self::put] after listing.
+    fn generation(&self) -> u64 {
+        self.listings.lock().unwrap().generation
+    }
+
+    /// Caches the listing of `key` and evicts expired listings.
+    ///
+    /// The listing is dropped if any invalidation happened since `generation`.
+    fn put(&self, key: ListKey, entries: Vec<oio::Entry>, generation: u64) {
+        let mut inner = self.listings.lock().unwrap();
+        if inner.generation != generation {
+            return;
+        }
+        let listing = Listing {
+            entries,
+            created: Instant::now(),
+        };
+        inner
+            .listings
+            .retain(|_, listing| listing.created.elapsed() < self.ttl);
+        let _ = inner.listings.insert(key, listing);
+    }
+
+    fn invalidate(&self, path: &str) {
+        invalidate(&self.listings, path);
+    }
+
+    fn invalidate_all(&self) {
+        let mut inner = self.listings.lock().unwrap();
+        inner.listings.clear();
+        inner.generation += 1;
+    }
+
+    fn new_writer<W>(&self, inner: W, path: &str) -> ListCacheWriter<W> {
+        ListCacheWriter {
+            inner,
+            path: path.to_string(),
+            listings: self.listings.clone(),
+        }
+    }
+}
+
+/// Writer and blocking writer of [ListCacheLayer] that invalidates listings on close.
+pub struct ListCacheWriter<W> {
+    inner: W,
+    path: String,
+    listings: Listings,
+}
+
+#[async_trait]
+impl<W: oio::Write> oio::Write for ListCacheWriter<W> {
+    async fn write(&mut self, bs: Bytes) -> Result<()> {
+        self.inner.write(bs).await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        let result = self.inner.close().await;
+        invalidate(&self.listings, &self.path);
+        result
+    }
+}
+
+impl<W: oio::BlockingWrite> oio::BlockingWrite for ListCacheWriter<W> {
+    fn write(&mut self, bs: Bytes) -> Result<()> {
+        self.inner.write(bs)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        let result = self.inner.close();
+        invalidate(&self.listings, &self.path);
+        result
+    }
+}
+
+/// A pager returning all cached entries in one page.
+struct CachedPager {
+    entries: Option<Vec<oio::Entry>>,
+}
+
+#[async_trait]
+impl Page for CachedPager {
+    async fn next(&mut self) -> Result<Option<Vec<oio::Entry>>> {
+        Ok(self.entries.take())
+    }
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for ListCacheAccessor<I> {
+    type Inner = I;
+    type Reader = I::Reader;
+    type BlockingReader = I::BlockingReader;
+    type Writer = ListCacheWriter<I::Writer>;
+    type BlockingWriter = ListCacheWriter<I::BlockingWriter>;
+    type Pager = oio::Pager;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.inner.read(path, args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.invalidate(path);
+        let (rp, writer) = self.inner.write(path, args).await?;
+        Ok((rp, self.new_writer(writer, path)))
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        self.invalidate(path);
+        self.inner.append(path, args).await
+    }
+
+    async fn create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        let result = self.inner.create_dir(path, args).await;
+        self.invalidate(path);
+        result
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        let result = self.inner.copy(from, to, args).await;
+        self.invalidate(to);
+        result
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        let result = self.inner.rename(from, to, args).await;
+        self.invalidate(from);
+        self.invalidate(to);
+        result
+    }
+
+    async fn delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        let result = self.inner.delete(path, args).await;
+        self.invalidate(path);
+        result
+    }
+
+    async fn batch(&self, args: OpBatch) -> Result<RpBatch> {
+        let result = self.inner.batch(args).await;
+        // A batch may touch any path, so we don't keep any listing.
+        self.invalidate_all();
+        result
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        let key = ListKey {
+            path: path.to_string(),
+            args: format!("{args:?}"),
+        };
+        if let Some(entries) = self.get(&key) {
+            let pager = CachedPager {
+                entries: Some(entries),
+            };
+            return Ok((RpList::default(), Box::new(pager)));
+        }
+
+        let generation = self.generation();
+        let (rp, mut pager) = self.inner.list(path, args).await?;
+        let mut entries = Vec::new();
+        while let Some(page) = pager.next().await? {
+            entries.extend(page);
+        }
+        self.put(key, entries.clone(), generation);
+
+        let pager = CachedPager {
+            entries: Some(entries),
+        };
+        Ok((rp, Box::new(pager)))
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.invalidate(path);
+        let (rp, writer) = self.inner.blocking_write(path, args)?;
+        Ok((rp, self.new_writer(writer, path)))
+    }
+
+    fn blocking_create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        let result = self.inner.blocking_create_dir(path, args);
+        self.invalidate(path);
+        result
+    }
+
+    fn blocking_copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        let result = self.inner.blocking_copy(from, to, args);
+        self.invalidate(to);
+        result
+    }
+
+    fn blocking_rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        let result = self.inner.blocking_rename(from, to, args);
+        self.invalidate(from);
+        self.invalidate(to);
+        result
+    }
+
+    fn blocking_delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        let result = self.inner.blocking_delete(path, args);
+        self.invalidate(path);
+        result
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use common_test_util::temp_dir::create_temp_dir;
+    use opendal::services::{Fs, Memory};
+    use opendal::{Builder, Operator};
+
+    use super::*;
+    use crate::util;
+
+    /// Counts list calls to the backend, and holds each listing until `gate` is
+    /// unlocked.
+    #[derive(Debug, Clone, Default)]
+    struct ListCounterLayer {
+        lists: Arc<AtomicUsize>,
+        gate: Arc<tokio::sync::Mutex<()>>,
+    }
+
+    impl<I: Accessor> Layer<I> for ListCounterLayer {
+        type LayeredAccessor = ListCounterAccessor<I>;
+
+        fn layer(&self, inner: I) -> Self::LayeredAccessor {
+            ListCounterAccessor {
+                inner,
+                layer: self.clone(),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct ListCounterAccessor<I> {
+        inner: I,
+        layer: ListCounterLayer,
+    }
+
+    #[async_trait]
+    impl<I: Accessor> LayeredAccessor for ListCounterAccessor<I> {
+        type Inner = I;
+        type Reader = I::Reader;
+        type BlockingReader = I::BlockingReader;
+        type Writer = I::Writer;
+        type BlockingWriter = I::BlockingWriter;
+        type Pager = oio::Pager;
+        type BlockingPager = I::BlockingPager;
+        type Appender = I::Appender;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+            self.inner.read(path, args).await
+        }
+
+        async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+            self.inner.write(path, args).await
+        }
+
+        async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+            self.inner.append(path, args).await
+        }
+
+        async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+            let (rp, mut pager) = self.inner.list(path, args).await?;
+            let mut entries = Vec::new();
+            while let Some(page) = pager.next().await? {
+                entries.extend(page);
+            }
+            let _ = self.layer.lists.fetch_add(1, Ordering::Relaxed);
+            let _guard = self.layer.gate.lock().await;
+            let pager = CachedPager {
+                entries: Some(entries),
+            };
+            Ok((rp, Box::new(pager)))
+        }
+
+        fn blocking_read(
+            &self,
+            path: &str,
+            args: OpRead,
+        ) -> Result<(RpRead, Self::BlockingReader)> {
+            self.inner.blocking_read(path, args)
+        }
+
+        fn blocking_write(
+            &self,
+            path: &str,
+            args: OpWrite,
+        ) -> Result<(RpWrite, Self::BlockingWriter)> {
+            self.inner.blocking_write(path, args)
+        }
+
+        fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+            self.inner.blocking_list(path, args)
+        }
+    }
+
+    fn new_store(lists: Arc<AtomicUsize>) -> Operator {
+        Operator::new(Memory::default())
+            .unwrap()
+            .layer(ListCounterLayer {
+                lists,
+                ..Default::default()
+            })
+            .layer(ListCacheLayer::new(Duration::from_millis(200)))
+            .finish()
+    }
+
+    async fn list_names(store: &Operator, path: &str) -> Vec<String> {
+        let entries = util::collect(store.list(path).await.unwrap())
+            .await
+            .unwrap();
+        let mut names: Vec<_> = entries.iter().map(|e| e.name().to_string()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_list_cache() {
+        let counter = ListCounterLayer::default();
+        let store = new_store(counter.lists.clone());
+        store.write("dir/a", "a").await.unwrap();
+        store.write("other/b", "b").await.unwrap();
+
+        assert_eq!(vec!["a"], list_names(&store, "dir/").await);
+        assert_eq!(vec!["a"], list_names(&store, "dir/").await);
+        assert_eq!(1, counter.lists.load(Ordering::Relaxed));
+
+        // Writing to another directory keeps the listing.
+        store.write("other/c", "c").await.unwrap();
+        assert_eq!(vec!["a"], list_names(&store, "dir/").await);
+        assert_eq!(1, counter.lists.load(Ordering::Relaxed));
+
+        // Writing to the directory invalidates the listing.
+        store.write("dir/d", "d").await.unwrap();
+        assert_eq!(vec!["a", "d"], list_names(&store, "dir/").await);
+        assert_eq!(2, counter.lists.load(Ordering::Relaxed));
+
+        // So does deleting.
+        store.delete("dir/a").await.unwrap();
+        assert_eq!(vec!["d"], list_names(&store, "dir/").await);
+        assert_eq!(3, counter.lists.load(Ordering::Relaxed));
+
+        // The listing expires after the ttl.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(vec!["d"], list_names(&store, "dir/").await);
+        assert_eq!(4, counter.lists.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_list_cache_invalidate_on_close() {
+        let lists = Arc::new(AtomicUsize::new(0));
+        let store = new_store(lists.clone());
+        store.write("dir/a", "a").await.unwrap();
+
+        let mut writer = store.writer("dir/b").await.unwrap();
+        writer.write("b").await.unwrap();
+        // Listing during the write caches a listing without the new object.
+        assert_eq!(vec!["a"], list_names(&store, "dir/").await);
+        assert_eq!(1, lists.load(Ordering::Relaxed));
+
+        writer.close().await.unwrap();
+        assert_eq!(vec!["a", "b"], list_names(&store, "dir/").await);
+        assert_eq!(2, lists.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_list_cache_evict_expired() {
+        let accessor = ListCacheLayer::new(Duration::from_millis(100))
+            .layer(Memory::default().build().unwrap());
+        let new_key = |path: &str| ListKey {
+            path: path.to_string(),
+            args: String::new(),
+        };
+        for i in 0..10 {
+            accessor.put(new_key(&format!("dir-{i}/")), Vec::new(), 0);
+        }
+        assert_eq!(10, accessor.listings.lock().unwrap().listings.len());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        accessor.put(new_key("other/"), Vec::new(), 0);
+        assert_eq!(1, accessor.listings.lock().unwrap().listings.len());
+
+        accessor.invalidate_all();
+        assert!(accessor.listings.lock().unwrap().listings.is_empty());
+
+        // Listings started before an invalidation are not cached.
+        accessor.put(new_key("other/"), Vec::new(), 0);
+        assert!(accessor.listings.lock().unwrap().listings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_cache_skip_stale_listing() {
+        let counter = ListCounterLayer::default();
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(counter.clone())
+            .layer(ListCacheLayer::new(Duration::from_secs(60)))
+            .finish();
+        store.write("dir/a", "a").await.unwrap();
+
+        // The listing reads the backend, then waits until the write finishes.
+        let guard = counter.gate.lock().await;
+        let list_task = {
+            let store = store.clone();
+            tokio::spawn(async move { list_names(&store, "dir/").await })
+        };
+        while counter.lists.load(Ordering::Relaxed) == 0 {
+            tokio::task::yield_now().await;
+        }
+        store.write("dir/b", "b").await.unwrap();
+        drop(guard);
+        assert_eq!(vec!["a"], list_task.await.unwrap());
+
+        // The stale listing is not cached.
+        assert_eq!(vec!["a", "b"], list_names(&store, "dir/").await);
+        assert_eq!(2, counter.lists.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_list_cache_invalidate_other_operations() {
+        let dir = create_temp_dir("test_list_cache_invalidate_other_operations");
+        let mut builder = Fs::default();
+        let _ = builder.root(dir.path().to_str().unwrap());
+        let lists = Arc::new(AtomicUsize::new(0));
+        let store = Operator::new(builder)
+            .unwrap()
+            .layer(ListCounterLayer {
+                lists: lists.clone(),
+                ..Default::default()
+            })
+            .layer(ListCacheLayer::new(Duration::from_secs(60)))
+            .finish();
+        store.write("dir/a", "a").await.unwrap();
+        assert_eq!(vec!["a"], list_names(&store, "dir/").await);
+        assert_eq!(vec!["a"], list_names(&store, "dir/").await);
+        assert_eq!(1, lists.load(Ordering::Relaxed));
+
+        store.create_dir("dir/sub/").await.unwrap();
+        assert_eq!(vec!["a", "sub/"], list_names(&store, "dir/").await);
+        assert_eq!(2, lists.load(Ordering::Relaxed));
+
+        store.copy("dir/a", "dir/b").await.unwrap();
+        assert_eq!(vec!["a", "b", "sub/"], list_names(&store, "dir/").await);
+        assert_eq!(3, lists.load(Ordering::Relaxed));
+
+        store.rename("dir/b", "dir/c").await.unwrap();
+        assert_eq!(vec!["a", "c", "sub/"], list_names(&store, "dir/").await);
+        assert_eq!(4, lists.load(Ordering::Relaxed));
+
+        let blocking = store.blocking();
+        blocking.write("dir/d", "d").unwrap();
+        assert_eq!(
+            vec!["a", "c", "d", "sub/"],
+            list_names(&store, "dir/").await
+        );
+        assert_eq!(5, lists.load(Ordering::Relaxed));
+
+        blocking.delete("dir/d").unwrap();
+        assert_eq!(vec!["a", "c", "sub/"], list_names(&store, "dir/").await);
+        assert_eq!(6, lists.load(Ordering::Relaxed));
+    }
+}
@@ -12,7 +12,6 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::num::NonZeroUsize;
 use std::ops::DerefMut;
 use std::sync::Arc;
 
@@ -21,8 +20,8 @@ use lru::LruCache;
 use metrics::increment_counter;
 use opendal::raw::oio::{Page, Read, ReadExt, Reader, Write};
 use opendal::raw::{
-    Accessor, Layer, LayeredAccessor, OpAppend, OpDelete, OpList, OpRead, OpWrite, RpAppend,
-    RpDelete, RpList, RpRead, RpWrite,
+    Accessor, Layer, LayeredAccessor, OpAppend, OpDelete, OpList, OpRead, OpStat, OpWrite,
+    RpAppend, RpDelete, RpList, RpRead, RpWrite,
 };
 use opendal::{ErrorKind, Result};
 use tokio::sync::Mutex;
@@ -32,19 +31,69 @@ use crate::metrics::{
     OBJECT_STORE_LRU_CACHE_MISS,
 };
 
+/// Cached files ordered by recency, bounded by their total size in bytes.
+#[derive(Debug)]
+struct CacheIndex {
+    /// Maps the cache file path to its size in bytes.
+    lru: LruCache<String, usize>,
+    /// Total size of cached files in bytes.
+    size: usize,
+    /// Max total size of cached files in bytes.
+    capacity: usize,
+}
+
+impl CacheIndex {
+    fn new(capacity: usize) -> Self {
+        Self {
+            lru: LruCache::unbounded(),
+            size: 0,
+            capacity,
+        }
+    }
+
+    /// Puts a cache file into the index and returns files evicted to keep
+    /// the total size under the capacity.
+    ///
+    /// The newly put file is never evicted, even if it is larger than the capacity.
+    fn put(&mut self, path: String, size: usize) -> Vec<String> {
+        if let Some(old_size) = self.lru.put(path, size) {
+            self.size -= old_size;
+        }
+        self.size += size;
+
+        let mut evicted = Vec::new();
+        while self.size > self.capacity && self.lru.len() > 1 {
+            if let Some((path, size)) = self.lru.pop_lru() {
+                self.size -= size;
+                evicted.push(path);
+            }
+        }
+        evicted
+    }
+
+    /// Removes a cache file from the index.
+    fn remove(&mut self, path: &str) {
+        if let Some(size) = self.lru.pop(path) {
+            self.size -= size;
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct LruCacheLayer<C> {
     cache: Arc<C>,
-    lru_cache: Arc<Mutex<LruCache<String, ()>>>,
+    lru_cache: Arc<Mutex<CacheIndex>>,
 }
 
 impl<C: Accessor + Clone> LruCacheLayer<C> {
+    /// Returns a new layer that caches reads in `cache`, keeping at most
+    /// `capacity` bytes of cached data.
+    ///
+    /// Files already in `cache` are indexed before returning, which stats each of them.
     pub async fn new(cache: Arc<C>, capacity: usize) -> Result<Self> {
         let layer = Self {
             cache,
-            lru_cache: Arc::new(Mutex::new(LruCache::new(
-                NonZeroUsize::new(capacity).unwrap(),
-            ))),
+            lru_cache: Arc::new(Mutex::new(CacheIndex::new(capacity))),
         };
         layer.recover_keys().await?;
 
@@ -52,13 +101,25 @@ impl<C: Accessor + Clone> LruCacheLayer<C> {
     }
 
     /// Recover existing keys from `cache` to `lru_cache`.
+    ///
+    /// This lists and stats every cached file, so its cost grows with the number of
+    /// files in `cache`.
     async fn recover_keys(&self) -> Result<()> {
         let (_, mut pager) = self.cache.list("/", OpList::default()).await?;
 
         let mut lru_cache = self.lru_cache.lock().await;
         while let Some(entries) = pager.next().await? {
             for entry in entries {
-                let _ = lru_cache.push(entry.path().to_string(), ());
+                let path = entry.path().to_string();
+                let size = self
+                    .cache
+                    .stat(&path, OpStat::default())
+                    .await?
+                    .into_metadata()
+                    .content_length() as usize;
+                for evicted in lru_cache.put(path, size) {
+                    let _ = self.cache.delete(&evicted, OpDelete::new()).await;
+                }
             }
         }
 
@@ -66,7 +127,12 @@ impl<C: Accessor + Clone> LruCacheLayer<C> {
     }
 
     pub async fn lru_contains_key(&self, key: &str) -> bool {
-        self.lru_cache.lock().await.contains(key)
+        self.lru_cache.lock().await.lru.contains(key)
+    }
+
+    /// Returns the total size of cached files in bytes.
+    pub async fn cached_size(&self) -> usize {
+        self.lru_cache.lock().await.size
     }
 }
 
@@ -86,7 +152,7 @@ impl<I: Accessor, C: Accessor> Layer<I> for LruCacheLayer<C> {
 pub struct LruCacheAccessor<I, C> {
     inner: I,
     cache: Arc<C>,
-    lru_cache: Arc<Mutex<LruCache<String, ()>>>,
+    lru_cache: Arc<Mutex<CacheIndex>>,
 }
 
 /// Returns true when the path of the file can be cached.
@@ -136,7 +202,7 @@ impl<I: Accessor, C: Accessor> LayeredAccessor for LruCacheAccessor<I, C> {
 
                 // update lru when cache hit
                 let mut lru_cache = lru_cache.lock().await;
-                let _ = lru_cache.get_or_insert(cache_path.clone(), || ());
+                let _ = lru_cache.lru.get(&cache_path);
                 Ok(to_output_reader((rp, r)))
             }
             Err(err) if err.kind() == ErrorKind::NotFound => {
@@ -145,21 +211,24 @@ impl<I: Accessor, C: Accessor> LayeredAccessor for LruCacheAccessor<I, C> {
                 let (_, mut reader) = self.inner.read(&path, args.clone()).await?;
                 let (_, mut writer) = self.cache.write(&cache_path, OpWrite::new()).await?;
 
+                let mut size = 0;
                 while let Some(bytes) = reader.next().await {
-                    writer.write(bytes?).await?;
+                    let bytes = bytes?;
+                    size += bytes.len();
+                    writer.write(bytes).await?;
                 }
 
                 writer.close().await?;
 
                 match self.cache.read(&cache_path, OpRead::default()).await {
                     Ok((rp, reader)) => {
-                        let r = {
+                        let evicted = {
                             // push new cache file name to lru
                             let mut lru_cache = lru_cache.lock().await;
-                            lru_cache.push(cache_path.clone(), ())
+                            lru_cache.put(cache_path.clone(), size)
                         };
-                        // delete the evicted cache file
-                        if let Some((k, _v)) = r {
+                        // delete the evicted cache files
+                        for k in evicted {
                             let _ = self.cache.delete(&k, OpDelete::new()).await;
                         }
                         return Ok(to_output_reader((rp, reader)));
@@ -188,14 +257,15 @@ impl<I: Accessor, C: Accessor> LayeredAccessor for LruCacheAccessor<I, C> {
 
         let cache_files: Vec<String> = {
             let mut guard = lru_cache.lock().await;
-            let lru = guard.deref_mut();
-            let cache_files = lru
+            let index = guard.deref_mut();
+            let cache_files = index
+                .lru
                 .iter()
                 .filter(|(k, _v)| k.starts_with(format!("{:x}.cache-", cache_path).as_str()))
                 .map(|(k, _v)| k.clone())
                 .collect::<Vec<_>>();
             for k in &cache_files {
-                let _ = lru.pop(k);
+                index.remove(k);
             }
             cache_files
         };
@@ -231,6 +301,29 @@ fn to_output_reader<R: Read + 'static>(input: (RpRead, R)) -> (RpRead, Reader) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cache_index_evict_by_size() {
+        let mut index = CacheIndex::new(10);
+        assert!(index.put("a".to_string(), 4).is_empty());
+        assert!(index.put("b".to_string(), 4).is_empty());
+        assert_eq!(8, index.size);
+
+        // Touch "a" so "b" is the least recently used file.
+        let _ = index.lru.get("a");
+        assert_eq!(vec!["b".to_string()], index.put("c".to_string(), 4));
+        assert_eq!(8, index.size);
+
+        // A file larger than the capacity evicts all others but itself.
+        let mut evicted = index.put("d".to_string(), 20);
+        evicted.sort();
+        assert_eq!(vec!["a".to_string(), "c".to_string()], evicted);
+        assert_eq!(20, index.size);
+
+        index.remove("d");
+        assert_eq!(0, index.size);
+        assert!(index.lru.is_empty());
+    }
+
     #[test]
     fn test_can_cache() {
         assert!(can_cache("test"));
@@ -0,0 +1,574 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use opendal::raw::{
+    oio, Accessor, Layer, LayeredAccessor, OpAppend, OpDelete, OpList, OpRead, OpStat, OpWrite,
+    RpAppend, RpDelete, RpList, RpRead, RpStat, RpWrite,
+};
+use opendal::{Error, Result};
+use tokio::sync::Notify;
+
+/// Default concurrency to start from.
+pub const DEFAULT_RAMP_UP_INITIAL_CONCURRENCY: usize = 4;
+/// Default number of successful operations to increase the concurrency by one.
+pub const DEFAULT_RAMP_UP_SUCCESSES_PER_STEP: usize = 8;
+
+/// A layer that starts with a low concurrency limit and ramps it up as
+/// operations succeed.
+///
+/// The limit grows by one every `successes_per_step` successful operations, up
+/// to the max concurrency. A temporary error, e.g. the backend asks to slow
+/// down, halves the limit. Operations started before the last halving don't
+/// halve it again, so a burst of concurrent throttles halves the limit once.
+/// This avoids throttling on a cold start while still reaching a high
+/// concurrency over time.
+///
+/// Readers, writers and pagers count as in flight until they are dropped, and
+/// errors they return are accounted too. A write succeeds once its writer is
+/// closed.
+///
+/// Clones of the layer share the same limit.
+#[derive(Debug, Clone)]
+pub struct RampUpConcurrencyLayer {
+    limiter: Arc<RampUpLimiter>,
+}
+
+impl RampUpConcurrencyLayer {
+    /// Returns a new layer ramping up from [DEFAULT_RAMP_UP_INITIAL_CONCURRENCY]
+    /// to `max_concurrency`.
+    pub fn new(max_concurrency: usize) -> Self {
+        let max_concurrency = max_concurrency.max(1);
+        Self::with_options(
+            DEFAULT_RAMP_UP_INITIAL_CONCURRENCY.min(max_concurrency),
+            max_concurrency,
+            DEFAULT_RAMP_UP_SUCCESSES_PER_STEP,
+        )
+    }
+
+    /// Returns a new layer ramping up from `initial` to `max` concurrency, one
+    /// step every `successes_per_step` successful operations.
+    pub fn with_options(initial: usize, max: usize, successes_per_step: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            limiter: Arc::new(RampUpLimiter {
+                state: Mutex::new(LimiterState {
+                    limit: initial.clamp(1, max),
+                    in_flight: 0,
+                    successes: 0,
+                    epoch: 0,
+                }),
+                max,
+                successes_per_step: successes_per_step.max(1),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// Returns the current concurrency limit.
+    pub fn limit(&self) -> usize {
+        self.limiter.state.lock().unwrap().limit
+    }
+}
+
+impl<I: Accessor> Layer<I> for RampUpConcurrencyLayer {
+    type LayeredAccessor = RampUpConcurrencyAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        RampUpConcurrencyAccessor {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    limit: usize,
+    in_flight: usize,
+    successes: usize,
+    /// Increased each time the limit is halved.
+    epoch: u64,
+}
+
+#[derive(Debug)]
+struct RampUpLimiter {
+    state: Mutex<LimiterState>,
+    max: usize,
+    successes_per_step: usize,
+    /// Notified when an operation may start.
+    notify: Notify,
+}
+
+impl RampUpLimiter {
+    /// Waits until the number of in-flight operations is below the limit.
+    async fn acquire(self: &Arc<Self>) -> InFlightGuard {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < state.limit {
+                    state.in_flight += 1;
+                    return InFlightGuard {
+                        limiter: self.clone(),
+                        epoch: state.epoch,
+                    };
+                }
+            }
+            notified.await;
+        }
+    }
+
+    fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.successes += 1;
+        if state.successes >= self.successes_per_step && state.limit < self.max {
+            state.limit += 1;
+            state.successes = 0;
+            self.notify.notify_one();
+        }
+    }
+
+    /// Halves the limit unless it was already halved after the throttled
+    /// operation started at `epoch`.
+    fn on_throttled(&self, epoch: u64) {
+        let mut state = self.state.lock().unwrap();
+        if epoch != state.epoch {
+            return;
+        }
+        state.limit = (state.limit / 2).max(1);
+        state.successes = 0;
+        state.epoch += 1;
+    }
+}
+
+/// Marks an in-flight operation, releases the slot on drop.
+struct InFlightGuard {
+    limiter: Arc<RampUpLimiter>,
+    /// The epoch of the limiter when the operation started.
+    epoch: u64,
+}
+
+impl InFlightGuard {
+    /// Adjusts the limit by the result of the operation.
+    fn on_result<T>(&self, result: &Result<T>) {
+        match result {
+            Ok(_) => self.limiter.on_success(),
+            Err(e) => self.on_error(e),
+        }
+    }
+
+    fn on_error(&self, e: &Error) {
+        if e.is_temporary() {
+            self.limiter.on_throttled(self.epoch);
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.limiter.state.lock().unwrap().in_flight -= 1;
+        self.limiter.notify.notify_one();
+    }
+}
+
+#[derive(Debug)]
+pub struct RampUpConcurrencyAccessor<I> {
+    inner: I,
+    limiter: Arc<RampUpLimiter>,
+}
+
+impl<I> RampUpConcurrencyAccessor<I> {
+    /// Runs the operation within the concurrency limit and adjusts the limit by its result.
+    async fn limit<T, F>(&self, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let guard = self.limiter.acquire().await;
+        let result = fut.await;
+        guard.on_result(&result);
+        result
+    }
+
+    /// Opens a reader, writer or pager within the concurrency limit. It stays in
+    /// flight until the returned wrapper is dropped.
+    ///
+    /// Only a failure is accounted if `wait_close` is true, the success is
+    /// accounted once the wrapper is closed.
+    async fn limit_io<Rp, R, F>(&self, fut: F, wait_close: bool) -> Result<(Rp, RampUpWrapper<R>)>
+    where
+        F: Future<Output = Result<(Rp, R)>>,
+    {
+        let guard = self.limiter.acquire().await;
+        let result = fut.await;
+        match &result {
+            Err(e) => guard.on_error(e),
+            Ok(_) if !wait_close => guard.on_result(&result),
+            Ok(_) => {}
+        }
+        result.map(|(rp, inner)| (rp, RampUpWrapper { inner, guard }))
+    }
+}
+
+/// Reader, writer or pager of [RampUpConcurrencyLayer] that stays in flight
+/// until it is dropped and reduces the limit on throttling.
+pub struct RampUpWrapper<R> {
+    inner: R,
+    guard: InFlightGuard,
+}
+
+impl<R: oio::Read> oio::Read for RampUpWrapper<R> {
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>> {
+        let poll = self.inner.poll_read(cx, buf);
+        if let Poll::Ready(Err(e)) = &poll {
+            self.guard.on_error(e);
+        }
+        poll
+    }
+
+    fn poll_seek(&mut self, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<Result<u64>> {
+        let poll = self.inner.poll_seek(cx, pos);
+        if let Poll::Ready(Err(e)) = &poll {
+            self.guard.on_error(e);
+        }
+        poll
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        let poll = self.inner.poll_next(cx);
+        if let Poll::Ready(Some(Err(e))) = &poll {
+            self.guard.on_error(e);
+        }
+        poll
+    }
+}
+
+#[async_trait]
+impl<W: oio::Write> oio::Write for RampUpWrapper<W> {
+    async fn write(&mut self, bs: Bytes) -> Result<()> {
+        let result = self.inner.write(bs).await;
+        if let Err(e) = &result {
+            self.guard.on_error(e);
+        }
+        result
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        let result = self.inner.close().await;
+        self.guard.on_result(&result);
+        result
+    }
+}
+
+#[async_trait]
+impl<P: oio::Page> oio::Page for RampUpWrapper<P> {
+    async fn next(&mut self) -> Result<Option<Vec<oio::Entry>>> {
+        let result = self.inner.next().await;
+        if let Err(e) = &result {
+            self.guard.on_error(e);
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for RampUpConcurrencyAccessor<I> {
+    type Inner = I;
+    type Reader = RampUpWrapper<I::Reader>;
+    type BlockingReader = I::BlockingReader;
+    type Writer = RampUpWrapper<I::Writer>;
+    type BlockingWriter = I::BlockingWriter;
+    type Pager = RampUpWrapper<I::Pager>;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.limit_io(self.inner.read(path, args), false).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.limit_io(self.inner.write(path, args), true).await
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        self.limit(self.inner.append(path, args)).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.limit(self.inner.stat(path, args)).await
+    }
+
+    async fn delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        self.limit(self.inner.delete(path, args)).await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        self.limit_io(self.inner.list(path, args), false).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures::future::try_join_all;
+    use opendal::services::Memory;
+    use opendal::{Error, ErrorKind, Operator};
+
+    use super::*;
+
+    /// A layer that throttles `stat` calls beyond `max_in_flight`, and closing
+    /// writers if `throttle_close` is true.
+    #[derive(Debug, Clone)]
+    struct ThrottleLayer {
+        max_in_flight: usize,
+        in_flight: Arc<AtomicUsize>,
+        throttled: Arc<AtomicUsize>,
+        throttle_close: bool,
+    }
+
+    struct ThrottleWriter<W> {
+        inner: W,
+        throttle_close: bool,
+    }
+
+    #[async_trait]
+    impl<W: oio::Write> oio::Write for ThrottleWriter<W> {
+        async fn write(&mut self, bs: Bytes) -> Result<()> {
+            self.inner.write(bs).await
+        }
+
+        async fn abort(&mut self) -> Result<()> {
+            self.inner.abort().await
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            if self.throttle_close {
+                return Err(Error::new(ErrorKind::Unexpected, "slow down").set_temporary());
+            }
+            self.inner.close().await
+        }
+    }
+
+    impl<I: Accessor> Layer<I> for ThrottleLayer {
+        type LayeredAccessor = ThrottleAccessor<I>;
+
+        fn layer(&self, inner: I) -> Self::LayeredAccessor {
+            ThrottleAccessor {
+                inner,
+                layer: self.clone(),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct ThrottleAccessor<I> {
+        inner: I,
+        layer: ThrottleLayer,
+    }
+
+    #[async_trait]
+    impl<I: Accessor> LayeredAccessor for ThrottleAccessor<I> {
+        type Inner = I;
+        type Reader = I::Reader;
+        type BlockingReader = I::BlockingReader;
+        type Writer = ThrottleWriter<I::Writer>;
+        type BlockingWriter = I::BlockingWriter;
+        type Pager = I::Pager;
+        type BlockingPager = I::BlockingPager;
+        type Appender = I::Appender;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+            self.inner.read(path, args).await
+        }
+
+        async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+            let (rp, inner) = self.inner.write(path, args).await?;
+            let writer = ThrottleWriter {
+                inner,
+                throttle_close: self.layer.throttle_close,
+            };
+            Ok((rp, writer))
+        }
+
+        async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+            self.inner.append(path, args).await
+        }
+
+        async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+            let current = self.layer.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            let result = if current > self.layer.max_in_flight {
+                let _ = self.layer.throttled.fetch_add(1, Ordering::SeqCst);
+                Err(Error::new(ErrorKind::Unexpected, "slow down").set_temporary())
+            } else {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.inner.stat(path, args).await
+            };
+            let _ = self.layer.in_flight.fetch_sub(1, Ordering::SeqCst);
+            result
+        }
+
+        async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+            self.inner.list(path, args).await
+        }
+
+        fn blocking_read(
+            &self,
+            path: &str,
+            args: OpRead,
+        ) -> Result<(RpRead, Self::BlockingReader)> {
+            self.inner.blocking_read(path, args)
+        }
+
+        fn blocking_write(
+            &self,
+            path: &str,
+            args: OpWrite,
+        ) -> Result<(RpWrite, Self::BlockingWriter)> {
+            self.inner.blocking_write(path, args)
+        }
+
+        fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+            self.inner.blocking_list(path, args)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_ramp_up_concurrency() {
+        let throttle = ThrottleLayer {
+            max_in_flight: 4,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            throttled: Arc::new(AtomicUsize::new(0)),
+            throttle_close: false,
+        };
+        let ramp_up = RampUpConcurrencyLayer::with_options(8, 8, 4);
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(throttle.clone())
+            .layer(ramp_up.clone())
+            .finish();
+        store.write("test_file", "Hello, World!").await.unwrap();
+
+        // Starting above what the backend accepts throttles and reduces the limit.
+        // Retries throttled operations so all of them complete.
+        let tasks = (0..32).map(|_| {
+            let store = store.clone();
+            tokio::spawn(async move {
+                loop {
+                    match store.stat("test_file").await {
+                        Ok(meta) => return meta,
+                        Err(e) if e.is_temporary() => {
+                            tokio::time::sleep(Duration::from_millis(1)).await;
+                        }
+                        Err(e) => panic!("unexpected error: {e}"),
+                    }
+                }
+            })
+        });
+        for meta in try_join_all(tasks).await.unwrap() {
+            assert_eq!(13, meta.content_length());
+        }
+        assert!(throttle.throttled.load(Ordering::SeqCst) > 0);
+        let reduced = ramp_up.limit();
+        assert!(reduced < 8, "limit: {reduced}");
+
+        // The limit grows back as operations succeed.
+        for _ in 0..20 {
+            let _ = store.stat("test_file").await.unwrap();
+        }
+        assert!(ramp_up.limit() > reduced, "limit: {}", ramp_up.limit());
+    }
+
+    #[tokio::test]
+    async fn test_ramp_up_halve_once_per_window() {
+        let ramp_up = RampUpConcurrencyLayer::with_options(8, 8, 4);
+        let limiter = ramp_up.limiter.clone();
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+        let throttled: Result<()> =
+            Err(Error::new(ErrorKind::Unexpected, "slow down").set_temporary());
+
+        // Operations started in the same window halve the limit once.
+        first.on_result(&throttled);
+        second.on_result(&throttled);
+        assert_eq!(4, ramp_up.limit());
+
+        // An operation started after the halving halves it again.
+        let third = limiter.acquire().await;
+        third.on_result(&throttled);
+        assert_eq!(2, ramp_up.limit());
+    }
+
+    #[tokio::test]
+    async fn test_ramp_up_writer() {
+        let throttle = ThrottleLayer {
+            max_in_flight: 1,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            throttled: Arc::new(AtomicUsize::new(0)),
+            throttle_close: true,
+        };
+        let ramp_up = RampUpConcurrencyLayer::with_options(2, 2, 1);
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(throttle)
+            .layer(ramp_up.clone())
+            .finish();
+
+        // The writer is in flight until it is dropped.
+        let mut writer = store.writer("test_file").await.unwrap();
+        let _other = store.writer("other_file").await.unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), store.stat("test_file"))
+                .await
+                .is_err()
+        );
+
+        // Throttling while closing the writer reduces the limit.
+        writer.write("Hello").await.unwrap();
+        let err = writer.close().await.unwrap_err();
+        assert!(err.is_temporary());
+        assert_eq!(1, ramp_up.limit());
+    }
+}
@@ -0,0 +1,602 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use opendal::raw::{
+    oio, Accessor, Layer, LayeredAccessor, OpAppend, OpBatch, OpCopy, OpCreateDir, OpDelete,
+    OpList, OpPresign, OpRead, OpRename, OpStat, OpWrite, RpAppend, RpBatch, RpCopy, RpCreateDir,
+    RpDelete, RpList, RpPresign, RpRead, RpRename, RpStat, RpWrite,
+};
+use opendal::Result;
+use tracing::{Instrument, Span};
+
+/// Name of spans created by [SampledTracingLayer].
+pub const OBJECT_STORE_SPAN_NAME: &str = "object_store_operation";
+
+/// A tracing layer that only traces a fraction of successful operations.
+///
+/// Failed operations are always traced, so sampling never hides errors. Reading,
+/// writing, appending and listing the content of a sampled operation runs inside
+/// its span. Spans are at debug level, like the ones of opendal's `TracingLayer`.
+#[derive(Debug, Clone, Copy)]
+pub struct SampledTracingLayer {
+    /// Ratio of successful operations to trace, in range `[0.0, 1.0]`.
+    sample_rate: f64,
+}
+
+impl SampledTracingLayer {
+    /// Returns a new layer tracing `sample_rate` of successful operations.
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            sample_rate: sample_rate.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Default for SampledTracingLayer {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl<I: Accessor> Layer<I> for SampledTracingLayer {
+    type LayeredAccessor = SampledTracingAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        SampledTracingAccessor {
+            inner,
+            sample_rate: self.sample_rate,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SampledTracingAccessor<I> {
+    inner: I,
+    sample_rate: f64,
+}
+
+impl<I> SampledTracingAccessor<I> {
+    fn should_sample(&self) -> bool {
+        rand::random::<f64>() < self.sample_rate
+    }
+
+    fn new_span(&self, operation: &'static str, path: &str) -> Option<Span> {
+        self.should_sample().then(|| new_span(operation, path))
+    }
+
+    /// Runs the operation, inside a span if sampled.
+    async fn trace<T, F>(&self, operation: &'static str, path: &str, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let span = self.new_span(operation, path);
+        run(span.as_ref(), operation, path, fut).await
+    }
+
+    /// Opens a reader, writer or pager, whose IO runs inside the span of the
+    /// operation if sampled.
+    async fn trace_io<Rp, R, F>(
+        &self,
+        operation: &'static str,
+        path: &str,
+        fut: F,
+    ) -> Result<(Rp, SampledTracingWrapper<R>)>
+    where
+        F: Future<Output = Result<(Rp, R)>>,
+    {
+        let span = self.new_span(operation, path);
+        let (rp, inner) = run(span.as_ref(), operation, path, fut).await?;
+        let wrapper = SampledTracingWrapper {
+            inner,
+            span,
+            operation,
+            path: path.to_string(),
+        };
+        Ok((rp, wrapper))
+    }
+
+    /// Blocking version of [SampledTracingAccessor::trace()].
+    fn blocking_trace<T>(
+        &self,
+        operation: &'static str,
+        path: &str,
+        f: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let span = self.new_span(operation, path);
+        blocking_run(span.as_ref(), operation, path, f)
+    }
+
+    /// Blocking version of [SampledTracingAccessor::trace_io()].
+    fn blocking_trace_io<Rp, R>(
+        &self,
+        operation: &'static str,
+        path: &str,
+        f: impl FnOnce() -> Result<(Rp, R)>,
+    ) -> Result<(Rp, SampledTracingWrapper<R>)> {
+        let span = self.new_span(operation, path);
+        let (rp, inner) = blocking_run(span.as_ref(), operation, path, f)?;
+        let wrapper = SampledTracingWrapper {
+            inner,
+            span,
+            operation,
+            path: path.to_string(),
+        };
+        Ok((rp, wrapper))
+    }
+}
+
+fn new_span(operation: &'static str, path: &str) -> Span {
+    tracing::debug_span!(OBJECT_STORE_SPAN_NAME, operation, path)
+}
+
+/// Runs `fut` inside `span` if any, and traces its failure.
+async fn run<T, F>(span: Option<&Span>, operation: &'static str, path: &str, fut: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let result = match span {
+        Some(span) => fut.instrument(span.clone()).await,
+        None => fut.await,
+    };
+    if let Err(e) = &result {
+        on_failure(span, operation, path, e);
+    }
+    result
+}
+
+/// Blocking version of [run()].
+fn blocking_run<T>(
+    span: Option<&Span>,
+    operation: &'static str,
+    path: &str,
+    f: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let result = match span {
+        Some(span) => span.in_scope(f),
+        None => f(),
+    };
+    if let Err(e) = &result {
+        on_failure(span, operation, path, e);
+    }
+    result
+}
+
+/// Traces a failure inside `span`, or inside a new span if the operation was not sampled.
+fn on_failure(span: Option<&Span>, operation: &'static str, path: &str, e: &opendal::Error) {
+    match span {
+        Some(span) => {
+            span.in_scope(|| tracing::debug!(error = %e, "object store operation failed"))
+        }
+        None => record_failure(operation, path, e),
+    }
+}
+
+/// Records a failed operation that was not sampled.
+fn record_failure(operation: &'static str, path: &str, e: &opendal::Error) {
+    new_span(operation, path)
+        .in_scope(|| tracing::debug!(error = %e, "object store operation failed"));
+}
+
+/// Reader, writer, appender or pager, blocking or not, of [SampledTracingLayer].
+pub struct SampledTracingWrapper<R> {
+    inner: R,
+    /// Span of the operation, `None` if not sampled.
+    span: Option<Span>,
+    operation: &'static str,
+    path: String,
+}
+
+impl<R> SampledTracingWrapper<R> {
+    fn observe<T>(&self, poll: &Poll<Result<T>>) {
+        if let Poll::Ready(Err(e)) = poll {
+            on_failure(self.span.as_ref(), self.operation, &self.path, e);
+        }
+    }
+
+    /// Runs blocking IO inside the span if any.
+    fn blocking_run<T>(&mut self, f: impl FnOnce(&mut R) -> Result<T>) -> Result<T> {
+        let inner = &mut self.inner;
+        blocking_run(self.span.as_ref(), self.operation, &self.path, || f(inner))
+    }
+}
+
+impl<R: oio::Read> oio::Read for SampledTracingWrapper<R> {
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>> {
+        let poll = {
+            let _entered = self.span.as_ref().map(Span::enter);
+            self.inner.poll_read(cx, buf)
+        };
+        self.observe(&poll);
+        poll
+    }
+
+    fn poll_seek(&mut self, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<Result<u64>> {
+        let poll = {
+            let _entered = self.span.as_ref().map(Span::enter);
+            self.inner.poll_seek(cx, pos)
+        };
+        self.observe(&poll);
+        poll
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        let poll = {
+            let _entered = self.span.as_ref().map(Span::enter);
+            self.inner.poll_next(cx)
+        };
+        if let Poll::Ready(Some(Err(e))) = &poll {
+            on_failure(self.span.as_ref(), self.operation, &self.path, e);
+        }
+        poll
+    }
+}
+
+#[async_trait]
+impl<W: oio::Write> oio::Write for SampledTracingWrapper<W> {
+    async fn write(&mut self, bs: Bytes) -> Result<()> {
+        run(
+            self.span.as_ref(),
+            self.operation,
+            &self.path,
+            self.inner.write(bs),
+        )
+        .await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        run(
+            self.span.as_ref(),
+            self.operation,
+            &self.path,
+            self.inner.abort(),
+        )
+        .await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        run(
+            self.span.as_ref(),
+            self.operation,
+            &self.path,
+            self.inner.close(),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<A: oio::Append> oio::Append for SampledTracingWrapper<A> {
+    async fn append(&mut self, bs: Bytes) -> Result<()> {
+        run(
+            self.span.as_ref(),
+            self.operation,
+            &self.path,
+            self.inner.append(bs),
+        )
+        .await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        run(
+            self.span.as_ref(),
+            self.operation,
+            &self.path,
+            self.inner.close(),
+        )
+        .await
+    }
+}
+
+impl<R: oio::BlockingRead> oio::BlockingRead for SampledTracingWrapper<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.blocking_run(|r| r.read(buf))
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.blocking_run(|r| r.seek(pos))
+    }
+
+    fn next(&mut self) -> Option<Result<Bytes>> {
+        let result = {
+            let _entered = self.span.as_ref().map(Span::enter);
+            self.inner.next()
+        };
+        if let Some(Err(e)) = &result {
+            on_failure(self.span.as_ref(), self.operation, &self.path, e);
+        }
+        result
+    }
+}
+
+impl<W: oio::BlockingWrite> oio::BlockingWrite for SampledTracingWrapper<W> {
+    fn write(&mut self, bs: Bytes) -> Result<()> {
+        self.blocking_run(|w| w.write(bs))
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.blocking_run(|w| w.close())
+    }
+}
+
+impl<P: oio::BlockingPage> oio::BlockingPage for SampledTracingWrapper<P> {
+    fn next(&mut self) -> Result<Option<Vec<oio::Entry>>> {
+        self.blocking_run(|p| p.next())
+    }
+}
+
+#[async_trait]
+impl<P: oio::Page> oio::Page for SampledTracingWrapper<P> {
+    async fn next(&mut self) -> Result<Option<Vec<oio::Entry>>> {
+        run(
+            self.span.as_ref(),
+            self.operation,
+            &self.path,
+            self.inner.next(),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for SampledTracingAccessor<I> {
+    type Inner = I;
+    type Reader = SampledTracingWrapper<I::Reader>;
+    type BlockingReader = SampledTracingWrapper<I::BlockingReader>;
+    type Writer = SampledTracingWrapper<I::Writer>;
+    type BlockingWriter = SampledTracingWrapper<I::BlockingWriter>;
+    type Pager = SampledTracingWrapper<I::Pager>;
+    type BlockingPager = SampledTracingWrapper<I::BlockingPager>;
+    type Appender = SampledTracingWrapper<I::Appender>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        self.trace("create_dir", path, self.inner.create_dir(path, args))
+            .await
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.trace_io("read", path, self.inner.read(path, args))
+            .await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.trace_io("write", path, self.inner.write(path, args))
+            .await
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        self.trace_io("append", path, self.inner.append(path, args))
+            .await
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        self.trace("copy", from, self.inner.copy(from, to, args))
+            .await
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        self.trace("rename", from, self.inner.rename(from, to, args))
+            .await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.trace("stat", path, self.inner.stat(path, args)).await
+    }
+
+    async fn delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        self.trace("delete", path, self.inner.delete(path, args))
+            .await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        self.trace_io("list", path, self.inner.list(path, args))
+            .await
+    }
+
+    async fn batch(&self, args: OpBatch) -> Result<RpBatch> {
+        self.trace("batch", "", self.inner.batch(args)).await
+    }
+
+    async fn presign(&self, path: &str, args: OpPresign) -> Result<RpPresign> {
+        self.trace("presign", path, self.inner.presign(path, args))
+            .await
+    }
+
+    fn blocking_create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        self.blocking_trace("blocking_create_dir", path, || {
+            self.inner.blocking_create_dir(path, args)
+        })
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.blocking_trace_io("blocking_read", path, || {
+            self.inner.blocking_read(path, args)
+        })
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.blocking_trace_io("blocking_write", path, || {
+            self.inner.blocking_write(path, args)
+        })
+    }
+
+    fn blocking_copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        self.blocking_trace("blocking_copy", from, || {
+            self.inner.blocking_copy(from, to, args)
+        })
+    }
+
+    fn blocking_rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        self.blocking_trace("blocking_rename", from, || {
+            self.inner.blocking_rename(from, to, args)
+        })
+    }
+
+    fn blocking_stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.blocking_trace("blocking_stat", path, || {
+            self.inner.blocking_stat(path, args)
+        })
+    }
+
+    fn blocking_delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        self.blocking_trace("blocking_delete", path, || {
+            self.inner.blocking_delete(path, args)
+        })
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.blocking_trace_io("blocking_list", path, || {
+            self.inner.blocking_list(path, args)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use common_test_util::temp_dir::create_temp_dir;
+    use futures::AsyncReadExt;
+    use opendal::services::{Fs, Memory};
+    use opendal::Operator;
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+
+    use super::*;
+
+    /// Counts object store spans.
+    struct SpanCounter(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> tracing_subscriber::Layer<S> for SpanCounter {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            if attrs.metadata().name() == OBJECT_STORE_SPAN_NAME {
+                assert_eq!(tracing::Level::DEBUG, *attrs.metadata().level());
+                let _ = self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Counts how many times any span is entered.
+    struct EnterCounter(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> tracing_subscriber::Layer<S> for EnterCounter {
+        fn on_enter(&self, _id: &Id, _ctx: Context<'_, S>) {
+            let _ = self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampled_tracing_layer() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry().with(SpanCounter(counter.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SampledTracingLayer::new(0.0))
+            .finish();
+
+        store.write("test_file", "Hello, World!").await.unwrap();
+        let _ = store.read("test_file").await.unwrap();
+        let _ = store.stat("test_file").await.unwrap();
+        assert_eq!(0, counter.load(Ordering::Relaxed));
+
+        // The failure is always traced.
+        let _ = store.read("not_exist").await.unwrap_err();
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+
+        // Trace all operations.
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SampledTracingLayer::new(1.0))
+            .finish();
+        let _ = store.stat("not_exist").await.unwrap_err();
+        store.write("test_file", "Hello, World!").await.unwrap();
+        assert!(counter.load(Ordering::Relaxed) >= 3);
+    }
+
+    #[tokio::test]
+    async fn test_sampled_tracing_io() {
+        let enters = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry().with(EnterCounter(enters.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SampledTracingLayer::new(1.0))
+            .finish();
+
+        // Closing the writer runs inside the span of the write.
+        let mut writer = store.writer("test_file").await.unwrap();
+        writer.write("Hello, World!").await.unwrap();
+        let before = enters.load(Ordering::Relaxed);
+        writer.close().await.unwrap();
+        assert!(enters.load(Ordering::Relaxed) > before);
+
+        // Reading the content runs inside the span of the read.
+        let mut reader = store.reader("test_file").await.unwrap();
+        let before = enters.load(Ordering::Relaxed);
+        let mut buf = Vec::new();
+        let _ = reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(b"Hello, World!", &buf[..]);
+        assert!(enters.load(Ordering::Relaxed) > before);
+    }
+    #[tokio::test]
+    async fn test_sampled_tracing_all_operations() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry().with(SpanCounter(counter.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let dir = create_temp_dir("test_sampled_tracing_all_operations");
+        let mut builder = Fs::default();
+        let _ = builder.root(dir.path().to_str().unwrap());
+        let store = Operator::new(builder)
+            .unwrap()
+            .layer(SampledTracingLayer::new(1.0))
+            .finish();
+
+        store.create_dir("dir/").await.unwrap();
+        assert_eq!(1, counter.load(Ordering::Relaxed));
+        store.write("dir/a", "a").await.unwrap();
+        assert_eq!(2, counter.load(Ordering::Relaxed));
+        store.copy("dir/a", "dir/b").await.unwrap();
+        assert_eq!(3, counter.load(Ordering::Relaxed));
+        store.rename("dir/b", "dir/c").await.unwrap();
+        assert_eq!(4, counter.load(Ordering::Relaxed));
+
+        let blocking = store.blocking();
+        let _ = blocking.stat("dir/c").unwrap();
+        assert_eq!(5, counter.load(Ordering::Relaxed));
+        blocking.delete("dir/c").unwrap();
+        assert_eq!(6, counter.load(Ordering::Relaxed));
+        let entries = blocking
+            .list("dir/")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(entries.iter().any(|entry| entry.path() == "dir/a"));
+        assert_eq!(7, counter.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,434 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::SeekFrom;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use opendal::raw::{
+    oio, Accessor, Layer, LayeredAccessor, OpAppend, OpDelete, OpList, OpRead, OpStat, OpWrite,
+    RpAppend, RpDelete, RpList, RpRead, RpStat, RpWrite,
+};
+use opendal::{Error, ErrorKind, Result};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default max time to wait for a permit.
+pub const DEFAULT_IO_LIMIT_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A layer that limits concurrent operations with a semaphore shared by all
+/// object stores using the same layer.
+///
+/// Unlike [ConcurrentLimitLayer](opendal::layers::ConcurrentLimitLayer), which
+/// creates a semaphore for each store, cloning this layer keeps a single budget,
+/// so a busy store can't starve the others on the same node.
+///
+/// A permit is held while the operation runs. For reads, writes and lists, the
+/// permit is held until the returned reader or pager reaches its end, the writer
+/// is closed or aborted, or any of them is dropped, so transferring the content
+/// counts against the limit too.
+///
+/// As a caller may keep readers open while running other operations, waiting
+/// for a permit fails with a temporary error after the acquire timeout instead
+/// of blocking forever.
+#[derive(Debug, Clone)]
+pub struct SharedConcurrentLimitLayer {
+    semaphore: Arc<Semaphore>,
+    permits: usize,
+    acquire_timeout: Duration,
+}
+
+impl SharedConcurrentLimitLayer {
+    /// Returns a new layer allowing `permits` concurrent operations in total.
+    pub fn new(permits: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(permits)),
+            permits,
+            acquire_timeout: DEFAULT_IO_LIMIT_ACQUIRE_TIMEOUT,
+        }
+    }
+
+    /// Sets the max time to wait for a permit.
+    pub fn with_acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// Returns the number of concurrent operations allowed.
+    pub fn permits(&self) -> usize {
+        self.permits
+    }
+}
+
+impl<I: Accessor> Layer<I> for SharedConcurrentLimitLayer {
+    type LayeredAccessor = SharedConcurrentLimitAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        SharedConcurrentLimitAccessor {
+            inner,
+            semaphore: self.semaphore.clone(),
+            acquire_timeout: self.acquire_timeout,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SharedConcurrentLimitAccessor<I> {
+    inner: I,
+    semaphore: Arc<Semaphore>,
+    acquire_timeout: Duration,
+}
+
+impl<I> SharedConcurrentLimitAccessor<I> {
+    async fn acquire(&self) -> Result<OwnedSemaphorePermit> {
+        match tokio::time::timeout(self.acquire_timeout, self.semaphore.clone().acquire_owned())
+            .await
+        {
+            Ok(result) => result.map_err(|e| {
+                Error::new(ErrorKind::Unexpected, "semaphore acquire failed").set_source(e)
+            }),
+            Err(_) => Err(Error::new(
+                ErrorKind::Unexpected,
+                "timed out waiting for the global io concurrency limit",
+            )
+            .with_context("timeout", format!("{:?}", self.acquire_timeout))
+            .set_temporary()),
+        }
+    }
+}
+
+/// Reader, writer or pager of [SharedConcurrentLimitLayer] that holds a permit
+/// until it is exhausted, closed or dropped.
+pub struct SharedConcurrentLimitWrapper<R> {
+    inner: R,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<R> SharedConcurrentLimitWrapper<R> {
+    fn new(inner: R, permit: OwnedSemaphorePermit) -> Self {
+        Self {
+            inner,
+            permit: Some(permit),
+        }
+    }
+
+    fn release(&mut self) {
+        self.permit = None;
+    }
+}
+
+impl<R: oio::Read> oio::Read for SharedConcurrentLimitWrapper<R> {
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>> {
+        let poll = self.inner.poll_read(cx, buf);
+        if matches!(poll, Poll::Ready(Ok(0))) && !buf.is_empty() {
+            self.release();
+        }
+        poll
+    }
+
+    fn poll_seek(&mut self, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<Result<u64>> {
+        self.inner.poll_seek(cx, pos)
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        let poll = self.inner.poll_next(cx);
+        if matches!(poll, Poll::Ready(None)) {
+            self.release();
+        }
+        poll
+    }
+}
+
+#[async_trait]
+impl<W: oio::Write> oio::Write for SharedConcurrentLimitWrapper<W> {
+    async fn write(&mut self, bs: Bytes) -> Result<()> {
+        self.inner.write(bs).await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        let result = self.inner.abort().await;
+        self.release();
+        result
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        let result = self.inner.close().await;
+        self.release();
+        result
+    }
+}
+
+#[async_trait]
+impl<P: oio::Page> oio::Page for SharedConcurrentLimitWrapper<P> {
+    async fn next(&mut self) -> Result<Option<Vec<oio::Entry>>> {
+        let entries = self.inner.next().await?;
+        if entries.is_none() {
+            self.release();
+        }
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for SharedConcurrentLimitAccessor<I> {
+    type Inner = I;
+    type Reader = SharedConcurrentLimitWrapper<I::Reader>;
+    type BlockingReader = I::BlockingReader;
+    type Writer = SharedConcurrentLimitWrapper<I::Writer>;
+    type BlockingWriter = I::BlockingWriter;
+    type Pager = SharedConcurrentLimitWrapper<I::Pager>;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        let permit = self.acquire().await?;
+        let (rp, reader) = self.inner.read(path, args).await?;
+        Ok((rp, SharedConcurrentLimitWrapper::new(reader, permit)))
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        let permit = self.acquire().await?;
+        let (rp, writer) = self.inner.write(path, args).await?;
+        Ok((rp, SharedConcurrentLimitWrapper::new(writer, permit)))
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        let _permit = self.acquire().await?;
+        self.inner.append(path, args).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        let _permit = self.acquire().await?;
+        self.inner.stat(path, args).await
+    }
+
+    async fn delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        let _permit = self.acquire().await?;
+        self.inner.delete(path, args).await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        let permit = self.acquire().await?;
+        let (rp, pager) = self.inner.list(path, args).await?;
+        Ok((rp, SharedConcurrentLimitWrapper::new(pager, permit)))
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures::future::try_join_all;
+    use futures::{AsyncReadExt, TryStreamExt};
+    use opendal::services::Memory;
+    use opendal::Operator;
+
+    use super::*;
+
+    /// Tracks the max number of in-flight `stat` calls shared by all stores.
+    #[derive(Debug, Clone, Default)]
+    struct InFlightLayer {
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl<I: Accessor> Layer<I> for InFlightLayer {
+        type LayeredAccessor = InFlightAccessor<I>;
+
+        fn layer(&self, inner: I) -> Self::LayeredAccessor {
+            InFlightAccessor {
+                inner,
+                layer: self.clone(),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct InFlightAccessor<I> {
+        inner: I,
+        layer: InFlightLayer,
+    }
+
+    #[async_trait]
+    impl<I: Accessor> LayeredAccessor for InFlightAccessor<I> {
+        type Inner = I;
+        type Reader = I::Reader;
+        type BlockingReader = I::BlockingReader;
+        type Writer = I::Writer;
+        type BlockingWriter = I::BlockingWriter;
+        type Pager = I::Pager;
+        type BlockingPager = I::BlockingPager;
+        type Appender = I::Appender;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+            self.inner.read(path, args).await
+        }
+
+        async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+            self.inner.write(path, args).await
+        }
+
+        async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+            self.inner.append(path, args).await
+        }
+
+        async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+            let current = self.layer.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            let _ = self
+                .layer
+                .max_in_flight
+                .fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let result = self.inner.stat(path, args).await;
+            let _ = self.layer.in_flight.fetch_sub(1, Ordering::SeqCst);
+            result
+        }
+
+        async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+            self.inner.list(path, args).await
+        }
+
+        fn blocking_read(
+            &self,
+            path: &str,
+            args: OpRead,
+        ) -> Result<(RpRead, Self::BlockingReader)> {
+            self.inner.blocking_read(path, args)
+        }
+
+        fn blocking_write(
+            &self,
+            path: &str,
+            args: OpWrite,
+        ) -> Result<(RpWrite, Self::BlockingWriter)> {
+            self.inner.blocking_write(path, args)
+        }
+
+        fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+            self.inner.blocking_list(path, args)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_shared_concurrent_limit() {
+        let in_flight = InFlightLayer::default();
+        let limit = SharedConcurrentLimitLayer::new(2);
+        let stores: Vec<_> = (0..2)
+            .map(|_| {
+                Operator::new(Memory::default())
+                    .unwrap()
+                    .layer(in_flight.clone())
+                    .layer(limit.clone())
+                    .finish()
+            })
+            .collect();
+        for store in &stores {
+            store.write("test_file", "Hello, World!").await.unwrap();
+        }
+
+        let tasks = stores.iter().flat_map(|store| {
+            (0..8).map(move |_| {
+                let store = store.clone();
+                tokio::spawn(async move { store.stat("test_file").await })
+            })
+        });
+        for result in try_join_all(tasks).await.unwrap() {
+            let _ = result.unwrap();
+        }
+
+        assert_eq!(0, in_flight.in_flight.load(Ordering::SeqCst));
+        assert_eq!(2, in_flight.max_in_flight.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_permit_released_by_reader_and_writer() {
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SharedConcurrentLimitLayer::new(1))
+            .finish();
+        store.write("test_file", "Hello, World!").await.unwrap();
+        let wait = Duration::from_millis(50);
+
+        // The reader holds the only permit until it reaches the end.
+        let mut reader = store.reader("test_file").await.unwrap();
+        assert!(tokio::time::timeout(wait, store.stat("test_file"))
+            .await
+            .is_err());
+        let mut buf = Vec::new();
+        let _ = reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(b"Hello, World!", &buf[..]);
+        let _ = store.stat("test_file").await.unwrap();
+        drop(reader);
+
+        // The writer holds it until it is closed.
+        let mut writer = store.writer("other_file").await.unwrap();
+        writer.write("Hello").await.unwrap();
+        assert!(tokio::time::timeout(wait, store.stat("test_file"))
+            .await
+            .is_err());
+        writer.close().await.unwrap();
+        let _ = store.stat("test_file").await.unwrap();
+        drop(writer);
+        assert_eq!(b"Hello", &store.read("other_file").await.unwrap()[..]);
+
+        // The pager holds it until the listing ends.
+        let mut lister = store.list("/").await.unwrap();
+        assert!(tokio::time::timeout(wait, store.stat("test_file"))
+            .await
+            .is_err());
+        while lister.try_next().await.unwrap().is_some() {}
+        let _ = store.stat("test_file").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_acquire_timeout() {
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(
+                SharedConcurrentLimitLayer::new(1).with_acquire_timeout(Duration::from_millis(50)),
+            )
+            .finish();
+        store.write("test_file", "Hello, World!").await.unwrap();
+
+        // An open reader doesn't block other operations forever.
+        let reader = store.reader("test_file").await.unwrap();
+        let err = store.stat("test_file").await.unwrap_err();
+        assert!(err.is_temporary());
+        drop(reader);
+        let _ = store.stat("test_file").await.unwrap();
+    }
+}
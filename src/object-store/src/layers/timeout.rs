@@ -0,0 +1,945 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use opendal::raw::{
+    oio, Accessor, Layer, LayeredAccessor, OpAppend, OpDelete, OpList, OpRead, OpStat, OpWrite,
+    RpAppend, RpDelete, RpList, RpRead, RpStat, RpWrite,
+};
+use opendal::{Error, ErrorKind, Result};
+
+/// Default max number of times to escalate the timeout.
+pub const DEFAULT_TIMEOUT_MAX_ESCALATIONS: usize = 2;
+/// Default factor to multiply the timeout by after each timed out attempt.
+pub const DEFAULT_TIMEOUT_ESCALATION_FACTOR: f64 = 2.0;
+/// Max factor to multiply the timeout by after each timed out attempt.
+pub const MAX_TIMEOUT_ESCALATION_FACTOR: f64 = 100.0;
+
+const MB: f64 = 1024.0 * 1024.0;
+
+tokio::task_local! {
+    /// Number of timed out attempts of the running operation.
+    static TIMED_OUT_ATTEMPTS: Cell<usize>;
+}
+
+/// A layer that applies a timeout to each attempt of an operation, and gives
+/// attempts a longer timeout after previous attempts of the same operation
+/// timed out.
+///
+/// The layer doesn't retry by itself, it is meant to be placed under a
+/// [RetryLayer](opendal::layers::RetryLayer) that retries the temporary timeout
+/// error. So the retry layer alone decides the number of attempts. The
+/// [EscalatingTimeoutScopeLayer] must be placed above the retry layer to track
+/// the timed out attempts of each operation, otherwise every attempt uses the
+/// initial timeout.
+///
+/// The first attempt of an operation uses the initial timeout. The timeout is
+/// multiplied by the escalation factor for each timed out attempt of the same
+/// operation, at most `max_escalations` times. Other operations running at the
+/// same time don't affect it. This tolerates occasional slow but successful
+/// operations without waiting long for every operation.
+///
+/// Only the operation itself is limited, e.g. the time to get a reader, not the
+/// time to read all its content.
+#[derive(Debug, Clone)]
+pub struct EscalatingTimeoutLayer {
+    timeout: Duration,
+    escalation_factor: f64,
+    max_escalations: usize,
+}
+
+impl EscalatingTimeoutLayer {
+    /// Returns a new layer whose attempts time out after `timeout` until one
+    /// times out.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            escalation_factor: DEFAULT_TIMEOUT_ESCALATION_FACTOR,
+            max_escalations: DEFAULT_TIMEOUT_MAX_ESCALATIONS,
+        }
+    }
+
+    /// Sets the factor to multiply the timeout by after each timed out attempt.
+    ///
+    /// Factors less than 1.0 or NaN are treated as 1.0, and factors larger than
+    /// [MAX_TIMEOUT_ESCALATION_FACTOR] are treated as the max.
+    pub fn with_escalation_factor(mut self, factor: f64) -> Self {
+        self.escalation_factor = if factor.is_nan() {
+            1.0
+        } else {
+            factor.clamp(1.0, MAX_TIMEOUT_ESCALATION_FACTOR)
+        };
+        self
+    }
+
+    /// Sets the max number of times to escalate the timeout.
+    pub fn with_max_escalations(mut self, max_escalations: usize) -> Self {
+        self.max_escalations = max_escalations;
+        self
+    }
+
+    /// Returns the timeout of an attempt after `timeouts` timed out attempts.
+    fn attempt_timeout(&self, timeouts: usize) -> Duration {
+        let escalations = timeouts.min(self.max_escalations) as f64;
+        let secs = self.timeout.as_secs_f64() * self.escalation_factor.powf(escalations);
+        Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
+    }
+}
+
+impl<I: Accessor> Layer<I> for EscalatingTimeoutLayer {
+    type LayeredAccessor = EscalatingTimeoutAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        EscalatingTimeoutAccessor {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EscalatingTimeoutAccessor<I> {
+    inner: I,
+    layer: EscalatingTimeoutLayer,
+}
+
+impl<I> EscalatingTimeoutAccessor<I> {
+    /// Runs `fut` as one attempt of the operation.
+    async fn timeout<T, F>(&self, operation: &'static str, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        // Operations outside of a scope always use the initial timeout.
+        let timeouts = TIMED_OUT_ATTEMPTS.try_with(Cell::get).unwrap_or(0);
+        let timeout = self.layer.attempt_timeout(timeouts);
+        match tokio::time::timeout(timeout, fut).await {
+            Ok(result) => result,
+            Err(_) => {
+                let _ = TIMED_OUT_ATTEMPTS.try_with(|n| n.set(n.get().saturating_add(1)));
+                Err(Error::new(ErrorKind::Unexpected, "operation timeout")
+                    .with_operation(operation)
+                    .with_context("timeout", format!("{timeout:?}"))
+                    .set_temporary())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for EscalatingTimeoutAccessor<I> {
+    type Inner = I;
+    type Reader = I::Reader;
+    type BlockingReader = I::BlockingReader;
+    type Writer = I::Writer;
+    type BlockingWriter = I::BlockingWriter;
+    type Pager = I::Pager;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.timeout("read", self.inner.read(path, args)).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.timeout("write", self.inner.write(path, args)).await
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        self.timeout("append", self.inner.append(path, args)).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.timeout("stat", self.inner.stat(path, args)).await
+    }
+
+    async fn delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        self.timeout("delete", self.inner.delete(path, args)).await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        self.timeout("list", self.inner.list(path, args)).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+/// A layer that tracks the timed out attempts of each operation for the
+/// [EscalatingTimeoutLayer] under it.
+///
+/// It must be placed above the [RetryLayer](opendal::layers::RetryLayer), so
+/// all retries of an operation run in the same scope.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EscalatingTimeoutScopeLayer;
+
+impl<I: Accessor> Layer<I> for EscalatingTimeoutScopeLayer {
+    type LayeredAccessor = EscalatingTimeoutScopeAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        EscalatingTimeoutScopeAccessor { inner }
+    }
+}
+
+#[derive(Debug)]
+pub struct EscalatingTimeoutScopeAccessor<I> {
+    inner: I,
+}
+
+/// Runs `fut`, including all its retries, as a new operation.
+async fn scope<T>(fut: impl Future<Output = Result<T>>) -> Result<T> {
+    TIMED_OUT_ATTEMPTS.scope(Cell::new(0), fut).await
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for EscalatingTimeoutScopeAccessor<I> {
+    type Inner = I;
+    type Reader = I::Reader;
+    type BlockingReader = I::BlockingReader;
+    type Writer = I::Writer;
+    type BlockingWriter = I::BlockingWriter;
+    type Pager = I::Pager;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        scope(self.inner.read(path, args)).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        scope(self.inner.write(path, args)).await
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        scope(self.inner.append(path, args)).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        scope(self.inner.stat(path, args)).await
+    }
+
+    async fn delete(&self, path: &str, args: OpDelete) -> Result<RpDelete> {
+        scope(self.inner.delete(path, args)).await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        scope(self.inner.list(path, args)).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+/// A layer that limits the time to write each chunk of data, and to read an
+/// object, by its size.
+///
+/// Writing a chunk times out after `base + per_mb * size_in_mb`, so large
+/// objects get proportionally more time than small ones. Reading uses the same
+/// budget for the whole content of the reader, sized by the requested range, or
+/// by the object size if the whole object is read. Closing a writer gets the
+/// budget of all bytes written, as buffered writers may upload them on close.
+/// The error after a timeout is temporary.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeAwareTimeoutLayer {
+    base: Duration,
+    per_mb: Duration,
+}
+
+impl SizeAwareTimeoutLayer {
+    /// Returns a new layer allowing `base` plus `per_mb` for each MB written or read.
+    pub fn new(base: Duration, per_mb: Duration) -> Self {
+        Self { base, per_mb }
+    }
+
+    /// Returns the timeout to write or read `size` bytes.
+    pub fn timeout(&self, size: usize) -> Duration {
+        self.base + self.per_mb.mul_f64(size as f64 / MB)
+    }
+}
+
+impl<I: Accessor> Layer<I> for SizeAwareTimeoutLayer {
+    type LayeredAccessor = SizeAwareTimeoutAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        SizeAwareTimeoutAccessor {
+            inner,
+            layer: *self,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SizeAwareTimeoutAccessor<I> {
+    inner: I,
+    layer: SizeAwareTimeoutLayer,
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for SizeAwareTimeoutAccessor<I> {
+    type Inner = I;
+    type Reader = SizeAwareTimeoutReader<I::Reader>;
+    type BlockingReader = I::BlockingReader;
+    type Writer = SizeAwareTimeoutWriter<I::Writer>;
+    type BlockingWriter = I::BlockingWriter;
+    type Pager = I::Pager;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        let range_size = args.range().size();
+        let (rp, reader) = self.inner.read(path, args).await?;
+        let size = range_size.unwrap_or_else(|| rp.metadata().content_length()) as usize;
+        let timeout = self.layer.timeout(size);
+        let reader = SizeAwareTimeoutReader {
+            inner: reader,
+            size,
+            timeout,
+            deadline: Box::pin(tokio::time::sleep(timeout)),
+        };
+        Ok((rp, reader))
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        let (rp, writer) = self.inner.write(path, args).await?;
+        let writer = SizeAwareTimeoutWriter {
+            inner: writer,
+            layer: self.layer,
+            written: 0,
+        };
+        Ok((rp, writer))
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        self.inner.append(path, args).await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        self.inner.list(path, args).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+/// Writer of [SizeAwareTimeoutLayer].
+pub struct SizeAwareTimeoutWriter<W> {
+    inner: W,
+    layer: SizeAwareTimeoutLayer,
+    /// Bytes written so far, which buffered writers may upload on close.
+    written: usize,
+}
+
+/// Reader of [SizeAwareTimeoutLayer].
+pub struct SizeAwareTimeoutReader<R> {
+    inner: R,
+    size: usize,
+    timeout: Duration,
+    /// Fires when the time to read the content is used up.
+    deadline: Pin<Box<tokio::time::Sleep>>,
+}
+
+impl<R> SizeAwareTimeoutReader<R> {
+    /// Returns an error if the deadline has passed, otherwise makes sure the
+    /// task is woken up when it passes.
+    fn poll_deadline(&mut self, cx: &mut Context<'_>, operation: &'static str) -> Result<()> {
+        match self.deadline.as_mut().poll(cx) {
+            Poll::Ready(()) => Err(timeout_error(
+                "read timeout",
+                operation,
+                self.size,
+                self.timeout,
+            )),
+            Poll::Pending => Ok(()),
+        }
+    }
+}
+
+impl<R: oio::Read> oio::Read for SizeAwareTimeoutReader<R> {
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>> {
+        if let Err(e) = self.poll_deadline(cx, "Reader::read") {
+            return Poll::Ready(Err(e));
+        }
+        self.inner.poll_read(cx, buf)
+    }
+
+    fn poll_seek(&mut self, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<Result<u64>> {
+        if let Err(e) = self.poll_deadline(cx, "Reader::seek") {
+            return Poll::Ready(Err(e));
+        }
+        self.inner.poll_seek(cx, pos)
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        if let Err(e) = self.poll_deadline(cx, "Reader::next") {
+            return Poll::Ready(Some(Err(e)));
+        }
+        self.inner.poll_next(cx)
+    }
+}
+
+fn write_timeout_error(operation: &'static str, size: usize, timeout: Duration) -> Error {
+    timeout_error("write timeout", operation, size, timeout)
+}
+
+fn timeout_error(
+    message: &'static str,
+    operation: &'static str,
+    size: usize,
+    timeout: Duration,
+) -> Error {
+    Error::new(ErrorKind::Unexpected, message)
+        .with_operation(operation)
+        .with_context("size", size.to_string())
+        .with_context("timeout", format!("{timeout:?}"))
+        .set_temporary()
+}
+
+#[async_trait]
+impl<W: oio::Write> oio::Write for SizeAwareTimeoutWriter<W> {
+    async fn write(&mut self, bs: Bytes) -> Result<()> {
+        let size = bs.len();
+        let timeout = self.layer.timeout(size);
+        tokio::time::timeout(timeout, self.inner.write(bs))
+            .await
+            .map_err(|_| write_timeout_error("Writer::write", size, timeout))??;
+        self.written += size;
+        Ok(())
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        let timeout = self.layer.timeout(self.written);
+        tokio::time::timeout(timeout, self.inner.close())
+            .await
+            .map_err(|_| write_timeout_error("Writer::close", self.written, timeout))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use opendal::layers::RetryLayer;
+    use opendal::services::Memory;
+    use opendal::Operator;
+
+    use super::*;
+
+    /// A layer that makes `stat` of paths starting with `slow` slow and counts
+    /// the calls to them.
+    #[derive(Debug, Clone)]
+    struct SlowStatLayer {
+        delay: Duration,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl<I: Accessor> Layer<I> for SlowStatLayer {
+        type LayeredAccessor = SlowStatAccessor<I>;
+
+        fn layer(&self, inner: I) -> Self::LayeredAccessor {
+            SlowStatAccessor {
+                inner,
+                layer: self.clone(),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct SlowStatAccessor<I> {
+        inner: I,
+        layer: SlowStatLayer,
+    }
+
+    #[async_trait]
+    impl<I: Accessor> LayeredAccessor for SlowStatAccessor<I> {
+        type Inner = I;
+        type Reader = I::Reader;
+        type BlockingReader = I::BlockingReader;
+        type Writer = I::Writer;
+        type BlockingWriter = I::BlockingWriter;
+        type Pager = I::Pager;
+        type BlockingPager = I::BlockingPager;
+        type Appender = I::Appender;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+            self.inner.read(path, args).await
+        }
+
+        async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+            self.inner.write(path, args).await
+        }
+
+        async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+            self.inner.append(path, args).await
+        }
+
+        async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+            if path.starts_with("slow") {
+                let _ = self.layer.calls.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(self.layer.delay).await;
+            }
+            self.inner.stat(path, args).await
+        }
+
+        async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+            self.inner.list(path, args).await
+        }
+
+        fn blocking_read(
+            &self,
+            path: &str,
+            args: OpRead,
+        ) -> Result<(RpRead, Self::BlockingReader)> {
+            self.inner.blocking_read(path, args)
+        }
+
+        fn blocking_write(
+            &self,
+            path: &str,
+            args: OpWrite,
+        ) -> Result<(RpWrite, Self::BlockingWriter)> {
+            self.inner.blocking_write(path, args)
+        }
+
+        fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+            self.inner.blocking_list(path, args)
+        }
+    }
+
+    fn new_store(
+        timeout_layer: EscalatingTimeoutLayer,
+        calls: Arc<AtomicUsize>,
+        retry_times: usize,
+    ) -> Operator {
+        Operator::new(Memory::default())
+            .unwrap()
+            .layer(SlowStatLayer {
+                delay: Duration::from_millis(300),
+                calls,
+            })
+            .layer(timeout_layer)
+            .layer(
+                RetryLayer::new()
+                    .with_max_times(retry_times)
+                    .with_min_delay(Duration::from_millis(1)),
+            )
+            .layer(EscalatingTimeoutScopeLayer)
+            .finish()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_escalating_timeout() {
+        // The first attempt times out after 100ms, the retry has 400ms.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let layer =
+            EscalatingTimeoutLayer::new(Duration::from_millis(100)).with_escalation_factor(4.0);
+        let store = new_store(layer, calls.clone(), 1);
+        store.write("slow_file", "Hello, World!").await.unwrap();
+
+        let meta = store.stat("slow_file").await.unwrap();
+        assert_eq!(13, meta.content_length());
+        assert_eq!(2, calls.load(Ordering::Relaxed));
+
+        // The next operation starts from the initial timeout again.
+        let meta = store.stat("slow_file").await.unwrap();
+        assert_eq!(13, meta.content_length());
+        assert_eq!(4, calls.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_escalating_timeout_with_concurrent_operations() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let layer =
+            EscalatingTimeoutLayer::new(Duration::from_millis(100)).with_escalation_factor(4.0);
+        let store = new_store(layer, calls.clone(), 1);
+        store.write("slow_file", "Hello, World!").await.unwrap();
+        store.write("fast_file", "Hello, World!").await.unwrap();
+
+        // Fast operations keep succeeding while the slow one is retried.
+        let fast = (0..4)
+            .map(|_| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    for _ in 0..50 {
+                        let _ = store.stat("fast_file").await.unwrap();
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let meta = store.stat("slow_file").await.unwrap();
+        assert_eq!(13, meta.content_length());
+        assert_eq!(2, calls.load(Ordering::Relaxed));
+        for task in fast {
+            task.await.unwrap();
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout_without_escalation() {
+        // Only the retry layer retries.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let layer =
+            EscalatingTimeoutLayer::new(Duration::from_millis(100)).with_escalation_factor(1.0);
+        let store = new_store(layer, calls.clone(), 1);
+        store.write("slow_file", "Hello, World!").await.unwrap();
+
+        let err = store.stat("slow_file").await.unwrap_err();
+        assert_eq!(ErrorKind::Unexpected, err.kind());
+        assert_eq!(2, calls.load(Ordering::Relaxed));
+
+        // Without a retry layer, the temporary error is returned after one attempt.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SlowStatLayer {
+                delay: Duration::from_millis(300),
+                calls: calls.clone(),
+            })
+            .layer(EscalatingTimeoutLayer::new(Duration::from_millis(100)))
+            .finish();
+        store.write("slow_file", "Hello, World!").await.unwrap();
+        let err = store.stat("slow_file").await.unwrap_err();
+        assert!(err.is_temporary());
+        assert_eq!(1, calls.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_attempt_timeout() {
+        let layer = EscalatingTimeoutLayer::new(Duration::from_millis(100))
+            .with_escalation_factor(4.0)
+            .with_max_escalations(2);
+        assert_eq!(Duration::from_millis(100), layer.attempt_timeout(0));
+        assert_eq!(Duration::from_millis(400), layer.attempt_timeout(1));
+        assert_eq!(Duration::from_millis(1600), layer.attempt_timeout(2));
+        assert_eq!(Duration::from_millis(1600), layer.attempt_timeout(10));
+
+        // Invalid factors don't panic.
+        for factor in [f64::NAN, f64::NEG_INFINITY, 0.5] {
+            let layer =
+                EscalatingTimeoutLayer::new(Duration::from_secs(1)).with_escalation_factor(factor);
+            assert_eq!(Duration::from_secs(1), layer.attempt_timeout(2));
+        }
+        let layer = EscalatingTimeoutLayer::new(Duration::MAX)
+            .with_escalation_factor(f64::INFINITY)
+            .with_max_escalations(usize::MAX);
+        assert_eq!(Duration::MAX, layer.attempt_timeout(usize::MAX));
+    }
+
+    /// A layer whose writers take `base + per_mb * size_in_mb` to write a chunk
+    /// and `close_per_mb` for each MB written to close, and whose readers take
+    /// `read_delay` before returning any content.
+    #[derive(Debug, Clone, Copy, Default)]
+    struct SlowIoLayer {
+        base: Duration,
+        per_mb: Duration,
+        close_per_mb: Duration,
+        read_delay: Duration,
+    }
+
+    impl<I: Accessor> Layer<I> for SlowIoLayer {
+        type LayeredAccessor = SlowIoAccessor<I>;
+
+        fn layer(&self, inner: I) -> Self::LayeredAccessor {
+            SlowIoAccessor {
+                inner,
+                layer: *self,
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct SlowIoAccessor<I> {
+        inner: I,
+        layer: SlowIoLayer,
+    }
+
+    #[async_trait]
+    impl<I: Accessor> LayeredAccessor for SlowIoAccessor<I> {
+        type Inner = I;
+        type Reader = SlowReader<I::Reader>;
+        type BlockingReader = I::BlockingReader;
+        type Writer = SlowWriter<I::Writer>;
+        type BlockingWriter = I::BlockingWriter;
+        type Pager = I::Pager;
+        type BlockingPager = I::BlockingPager;
+        type Appender = I::Appender;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+            let (rp, reader) = self.inner.read(path, args).await?;
+            let reader = SlowReader {
+                inner: reader,
+                delay: Box::pin(tokio::time::sleep(self.layer.read_delay)),
+            };
+            Ok((rp, reader))
+        }
+
+        async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+            let (rp, writer) = self.inner.write(path, args).await?;
+            let writer = SlowWriter {
+                inner: writer,
+                layer: self.layer,
+                written: 0,
+            };
+            Ok((rp, writer))
+        }
+
+        async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+            self.inner.append(path, args).await
+        }
+
+        async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+            self.inner.list(path, args).await
+        }
+
+        fn blocking_read(
+            &self,
+            path: &str,
+            args: OpRead,
+        ) -> Result<(RpRead, Self::BlockingReader)> {
+            self.inner.blocking_read(path, args)
+        }
+
+        fn blocking_write(
+            &self,
+            path: &str,
+            args: OpWrite,
+        ) -> Result<(RpWrite, Self::BlockingWriter)> {
+            self.inner.blocking_write(path, args)
+        }
+
+        fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+            self.inner.blocking_list(path, args)
+        }
+    }
+
+    struct SlowReader<R> {
+        inner: R,
+        delay: Pin<Box<tokio::time::Sleep>>,
+    }
+
+    impl<R: oio::Read> oio::Read for SlowReader<R> {
+        fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>> {
+            futures::ready!(self.delay.as_mut().poll(cx));
+            self.inner.poll_read(cx, buf)
+        }
+
+        fn poll_seek(&mut self, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<Result<u64>> {
+            futures::ready!(self.delay.as_mut().poll(cx));
+            self.inner.poll_seek(cx, pos)
+        }
+
+        fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+            futures::ready!(self.delay.as_mut().poll(cx));
+            self.inner.poll_next(cx)
+        }
+    }
+
+    struct SlowWriter<W> {
+        inner: W,
+        layer: SlowIoLayer,
+        written: usize,
+    }
+
+    #[async_trait]
+    impl<W: oio::Write> oio::Write for SlowWriter<W> {
+        async fn write(&mut self, bs: Bytes) -> Result<()> {
+            let delay = self.layer.base + self.layer.per_mb.mul_f64(bs.len() as f64 / MB);
+            tokio::time::sleep(delay).await;
+            self.written += bs.len();
+            self.inner.write(bs).await
+        }
+
+        async fn abort(&mut self) -> Result<()> {
+            self.inner.abort().await
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            let delay = self.layer.close_per_mb.mul_f64(self.written as f64 / MB);
+            tokio::time::sleep(delay).await;
+            self.inner.close().await
+        }
+    }
+
+    #[test]
+    fn test_size_aware_timeout() {
+        let layer = SizeAwareTimeoutLayer::new(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(Duration::from_millis(100), layer.timeout(0));
+        assert_eq!(Duration::from_millis(600), layer.timeout(512 * 1024));
+        assert_eq!(Duration::from_millis(4100), layer.timeout(4 * 1024 * 1024));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_size_aware_write_timeout() {
+        // Takes 20ms plus 50ms per MB to write, the timeout is 100ms plus 100ms per MB.
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SlowIoLayer {
+                base: Duration::from_millis(20),
+                per_mb: Duration::from_millis(50),
+                ..Default::default()
+            })
+            .layer(SizeAwareTimeoutLayer::new(
+                Duration::from_millis(100),
+                Duration::from_millis(100),
+            ))
+            .finish();
+
+        // A large object takes 220ms, which is longer than the base timeout but within
+        // its own deadline.
+        let large = vec![1u8; 4 * 1024 * 1024];
+        store.write("large", large.clone()).await.unwrap();
+        assert_eq!(large, store.read("large").await.unwrap());
+        store.write("small", "Hello, World!").await.unwrap();
+
+        // A small object that takes as long as the large one times out.
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SlowIoLayer {
+                base: Duration::from_millis(220),
+                per_mb: Duration::ZERO,
+                ..Default::default()
+            })
+            .layer(SizeAwareTimeoutLayer::new(
+                Duration::from_millis(100),
+                Duration::from_millis(100),
+            ))
+            .finish();
+        let err = store.write("small", "Hello, World!").await.unwrap_err();
+        assert_eq!(ErrorKind::Unexpected, err.kind());
+        assert!(err.is_temporary());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_size_aware_read_timeout() {
+        // Takes 220ms to read, the timeout is 100ms plus 100ms per MB.
+        let backend = Operator::new(Memory::default()).unwrap().finish();
+        let large = vec![1u8; 4 * 1024 * 1024];
+        backend.write("large", large.clone()).await.unwrap();
+        backend.write("small", "Hello, World!").await.unwrap();
+        let store = backend
+            .layer(SlowIoLayer {
+                read_delay: Duration::from_millis(220),
+                ..Default::default()
+            })
+            .layer(SizeAwareTimeoutLayer::new(
+                Duration::from_millis(100),
+                Duration::from_millis(100),
+            ));
+
+        // Reading the large object is within its deadline.
+        assert_eq!(large, store.read("large").await.unwrap());
+
+        // Reading the small object, or a small range of the large one, times out.
+        let err = store.read("small").await.unwrap_err();
+        assert_eq!(ErrorKind::Unexpected, err.kind());
+        assert!(err.is_temporary());
+        let err = store.range_read("large", 0..13).await.unwrap_err();
+        assert!(err.is_temporary());
+    }
+    #[tokio::test(start_paused = true)]
+    async fn test_size_aware_close_timeout() {
+        // Uploads on close, taking 50ms per MB written.
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SlowIoLayer {
+                close_per_mb: Duration::from_millis(50),
+                ..Default::default()
+            })
+            .layer(SizeAwareTimeoutLayer::new(
+                Duration::from_millis(100),
+                Duration::from_millis(100),
+            ))
+            .finish();
+
+        // Closing takes 200ms, which is within the budget of the 4MB written.
+        let large = vec![1u8; 4 * 1024 * 1024];
+        store.write("large", large.clone()).await.unwrap();
+        assert_eq!(large, store.read("large").await.unwrap());
+
+        // Closing still times out if it's slower than the budget of the bytes written.
+        let store = Operator::new(Memory::default())
+            .unwrap()
+            .layer(SlowIoLayer {
+                close_per_mb: Duration::from_millis(500),
+                ..Default::default()
+            })
+            .layer(SizeAwareTimeoutLayer::new(
+                Duration::from_millis(100),
+                Duration::from_millis(100),
+            ))
+            .finish();
+        let err = store.write("large", large).await.unwrap_err();
+        assert!(err.is_temporary());
+    }
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 pub use opendal::raw::normalize_path as raw_normalize_path;
+pub use opendal::raw::HttpClient;
 pub use opendal::raw::oio::Pager;
 pub use opendal::{
     services, Builder as ObjectStoreBuilder, Entry, EntryMode, Error, ErrorKind, Metakey,
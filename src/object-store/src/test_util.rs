@@ -14,6 +14,14 @@
 
 use std::env;
 
+use async_trait::async_trait;
+use bytes::Bytes;
+use opendal::raw::oio;
+use opendal::raw::{
+    Accessor, Layer, LayeredAccessor, OpAppend, OpList, OpRead, OpWrite, RpAppend, RpList, RpRead,
+    RpWrite,
+};
+
 use crate::{ObjectStore, Result};
 
 /// Temp folder for object store test
@@ -62,3 +70,98 @@ pub fn s3_test_config() -> Option<TestS3Config> {
 
     None
 }
+
+/// A layer that corrupts every object written through it by flipping the bits
+/// of its first byte, to test detecting corrupted writes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CorruptWriteLayer;
+
+impl<I: Accessor> Layer<I> for CorruptWriteLayer {
+    type LayeredAccessor = CorruptWriteAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        CorruptWriteAccessor { inner }
+    }
+}
+
+#[derive(Debug)]
+pub struct CorruptWriteAccessor<I> {
+    inner: I,
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for CorruptWriteAccessor<I> {
+    type Inner = I;
+    type Reader = I::Reader;
+    type BlockingReader = I::BlockingReader;
+    type Writer = CorruptWriter<I::Writer>;
+    type BlockingWriter = I::BlockingWriter;
+    type Pager = I::Pager;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.inner.read(path, args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        let (rp, writer) = self.inner.write(path, args).await?;
+        let writer = CorruptWriter {
+            inner: writer,
+            corrupted: false,
+        };
+        Ok((rp, writer))
+    }
+
+    async fn append(&self, path: &str, args: OpAppend) -> Result<(RpAppend, Self::Appender)> {
+        self.inner.append(path, args).await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Pager)> {
+        self.inner.list(path, args).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+/// Writer of [CorruptWriteLayer].
+pub struct CorruptWriter<W> {
+    inner: W,
+    corrupted: bool,
+}
+
+#[async_trait]
+impl<W: oio::Write> oio::Write for CorruptWriter<W> {
+    async fn write(&mut self, bs: Bytes) -> Result<()> {
+        if self.corrupted || bs.is_empty() {
+            return self.inner.write(bs).await;
+        }
+
+        self.corrupted = true;
+        let mut data = bs.to_vec();
+        data[0] = !data[0];
+        self.inner.write(Bytes::from(data)).await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+}
@@ -13,18 +13,23 @@
 // limitations under the License.
 
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use async_trait::async_trait;
 use common_telemetry::{logging, metric};
 use common_test_util::temp_dir::create_temp_dir;
-use object_store::layers::LruCacheLayer;
+use object_store::layers::{LruCacheLayer, MetricsLayer, RetryLayer};
 use object_store::services::{Fs, S3};
 use object_store::test_util::TempFolder;
 use object_store::{util, ObjectStore, ObjectStoreBuilder};
-use opendal::raw::Accessor;
-use opendal::services::{Azblob, Gcs, Oss};
-use opendal::{EntryMode, Operator, OperatorBuilder};
+use opendal::raw::{
+    Accessor, Layer, LayeredAccessor, OpAppend, OpList, OpRead, OpStat, OpWrite, RpAppend, RpList,
+    RpRead, RpStat, RpWrite,
+};
+use opendal::services::{Azblob, Gcs, Memory, Oss};
+use opendal::{EntryMode, Error, ErrorKind, Operator, OperatorBuilder};
 
 async fn test_object_crud(store: &ObjectStore) -> Result<()> {
     // Create object handler.
@@ -271,7 +276,7 @@ async fn test_object_store_cache_policy() -> Result<()> {
     let cache_store = OperatorBuilder::new(cache_accessor.clone()).finish();
 
     // create operator for cache dir to verify cache file
-    let cache_layer = LruCacheLayer::new(Arc::new(cache_accessor.clone()), 3)
+    let cache_layer = LruCacheLayer::new(Arc::new(cache_accessor.clone()), 1024)
         .await
         .unwrap();
     let store = store.layer(cache_layer.clone());
@@ -356,7 +361,7 @@ async fn test_object_store_cache_policy() -> Result<()> {
     assert!(metric_text.contains("object_store_lru_cache_miss"));
 
     drop(cache_layer);
-    let cache_layer = LruCacheLayer::new(Arc::new(cache_accessor), 3)
+    let cache_layer = LruCacheLayer::new(Arc::new(cache_accessor), 1024)
         .await
         .unwrap();
 
@@ -372,3 +377,222 @@ async fn test_object_store_cache_policy() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_object_store_cache_capacity() -> Result<()> {
+    let root_dir = create_temp_dir("test_object_store_cache_capacity");
+    let store = OperatorBuilder::new(
+        Fs::default()
+            .root(&root_dir.path().to_string_lossy())
+            .atomic_write_dir(&root_dir.path().to_string_lossy())
+            .build()
+            .unwrap(),
+    )
+    .finish();
+
+    let cache_dir = create_temp_dir("test_object_store_cache_capacity_cache");
+    let mut builder = Fs::default();
+    let _ = builder
+        .root(&cache_dir.path().to_string_lossy())
+        .atomic_write_dir(&cache_dir.path().to_string_lossy());
+    let cache_accessor = Arc::new(builder.build().unwrap());
+    // Only one of the files below fits in the cache.
+    let cache_layer = LruCacheLayer::new(Arc::new(cache_accessor), 20)
+        .await
+        .unwrap();
+    let cached_store = store.clone().layer(cache_layer.clone());
+
+    let p1 = "test_file1";
+    let p2 = "test_file2";
+    store.write(p1, "Hello, object1!").await?;
+    store.write(p2, "Hello, object2!").await?;
+
+    let _ = cached_store.read(p1).await?;
+    assert_eq!(15, cache_layer.cached_size().await);
+    let key1 = "6d29752bdc6e4d5ba5483b96615d6c48.cache-bytes=0-";
+    assert!(cache_layer.lru_contains_key(key1).await);
+
+    // Removes the object from the backend only, so reads must be served by the cache.
+    store.delete(p1).await?;
+    assert_eq!(b"Hello, object1!", cached_store.read(p1).await?.as_slice());
+
+    // Caching another file exceeds the capacity and evicts the first one.
+    let _ = cached_store.read(p2).await?;
+    assert_eq!(15, cache_layer.cached_size().await);
+    assert!(!cache_layer.lru_contains_key(key1).await);
+    assert!(
+        cache_layer
+            .lru_contains_key("ecfe0dce85de452eb0a325158e7bfb75.cache-bytes=0-")
+            .await
+    );
+    assert!(cached_store.read(p1).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_object_store_metrics() -> Result<()> {
+    common_telemetry::init_default_metrics_recorder();
+    let store = Operator::new(Memory::default())?
+        .layer(MetricsLayer)
+        .finish();
+
+    store.write("test_file", "Hello, World!").await?;
+    let _ = store.read("test_file").await?;
+
+    let metric_text = metric::try_handle().unwrap().render();
+    // Operations are labeled by the backend and the operation.
+    for pattern in [
+        "opendal_requests_total",
+        "service=\"memory\"",
+        "operation=\"write\"",
+    ] {
+        assert!(
+            metric_text.contains(pattern),
+            "{pattern} not in {metric_text}"
+        );
+    }
+    assert!(metric_text.contains("opendal_requests_duration_seconds"));
+
+    Ok(())
+}
+
+/// A layer that fails the first `failures` stat calls with temporary errors and
+/// records when each call happens.
+#[derive(Debug, Clone)]
+struct FlakyStatLayer {
+    failures: usize,
+    calls: Arc<Mutex<Vec<Instant>>>,
+}
+
+impl<I: Accessor> Layer<I> for FlakyStatLayer {
+    type LayeredAccessor = FlakyStatAccessor<I>;
+
+    fn layer(&self, inner: I) -> Self::LayeredAccessor {
+        FlakyStatAccessor {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FlakyStatAccessor<I> {
+    inner: I,
+    layer: FlakyStatLayer,
+}
+
+#[async_trait]
+impl<I: Accessor> LayeredAccessor for FlakyStatAccessor<I> {
+    type Inner = I;
+    type Reader = I::Reader;
+    type BlockingReader = I::BlockingReader;
+    type Writer = I::Writer;
+    type BlockingWriter = I::BlockingWriter;
+    type Pager = I::Pager;
+    type BlockingPager = I::BlockingPager;
+    type Appender = I::Appender;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> opendal::Result<(RpRead, Self::Reader)> {
+        self.inner.read(path, args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> opendal::Result<(RpWrite, Self::Writer)> {
+        self.inner.write(path, args).await
+    }
+
+    async fn append(
+        &self,
+        path: &str,
+        args: OpAppend,
+    ) -> opendal::Result<(RpAppend, Self::Appender)> {
+        self.inner.append(path, args).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> opendal::Result<RpStat> {
+        let attempt = {
+            let mut calls = self.layer.calls.lock().unwrap();
+            calls.push(Instant::now());
+            calls.len()
+        };
+        if attempt <= self.layer.failures {
+            return Err(Error::new(ErrorKind::Unexpected, "injected failure").set_temporary());
+        }
+        self.inner.stat(path, args).await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> opendal::Result<(RpList, Self::Pager)> {
+        self.inner.list(path, args).await
+    }
+
+    fn blocking_read(
+        &self,
+        path: &str,
+        args: OpRead,
+    ) -> opendal::Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(
+        &self,
+        path: &str,
+        args: OpWrite,
+    ) -> opendal::Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_list(
+        &self,
+        path: &str,
+        args: OpList,
+    ) -> opendal::Result<(RpList, Self::BlockingPager)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+#[tokio::test]
+async fn test_retry_backoff_bounds() -> Result<()> {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let initial_backoff = Duration::from_millis(20);
+    let max_backoff = Duration::from_millis(50);
+    let store = Operator::new(Memory::default())?
+        .layer(FlakyStatLayer {
+            failures: 4,
+            calls: calls.clone(),
+        })
+        .layer(
+            RetryLayer::new()
+                .with_min_delay(initial_backoff)
+                .with_max_delay(max_backoff)
+                .with_max_times(4),
+        )
+        .finish();
+    store.write("test_file", "Hello, World!").await?;
+
+    let meta = store.stat("test_file").await?;
+    assert_eq!(13, meta.content_length());
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(5, calls.len());
+    // Backoff doubles from the initial backoff and is capped by the max backoff.
+    let expected = [20, 40, 50, 50].map(Duration::from_millis);
+    for (i, window) in calls.windows(2).enumerate() {
+        let elapsed = window[1] - window[0];
+        assert!(
+            elapsed >= expected[i],
+            "backoff {i} is {elapsed:?}, expect at least {:?}",
+            expected[i]
+        );
+        assert!(
+            elapsed < expected[i] + Duration::from_millis(200),
+            "backoff {i} is {elapsed:?}, expect about {:?}",
+            expected[i]
+        );
+    }
+
+    Ok(())
+}
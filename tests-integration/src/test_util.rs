@@ -248,6 +248,7 @@ pub fn get_test_store_config(
             (
                 ObjectStoreConfig::File(FileConfig {
                     data_home: data_tmp_dir.path().to_str().unwrap().to_string(),
+                    atomic_write_dir: None,
                 }),
                 TempDirGuard::File(data_tmp_dir),
             )